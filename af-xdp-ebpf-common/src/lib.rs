@@ -1,3 +1,35 @@
 #![no_std]
 
 pub const SOCKS_MAP_SIZE: u32 = 5;
+
+/// Packets without a socket are passed on to the network stack (`XDP_PASS`).
+pub const FALLBACK_PASS: u32 = 0;
+/// Packets without a socket are dropped (`XDP_DROP`).
+pub const FALLBACK_DROP: u32 = 1;
+/// Packets without a socket are dropped and reported through the `xdp:xdp_exception` tracepoint (`XDP_ABORTED`).
+pub const FALLBACK_ABORT: u32 = 2;
+
+/// The socket of a packet is at the index of the queue id it was received on.
+pub const INDEX_QUEUE_ID: u32 = 0;
+/// The socket of a packet is at `queue_id * sockets_per_queue + flow_hash % sockets_per_queue`.
+///
+/// The flow hash covers the addresses, protocol and TCP or UDP ports of IPv4 and IPv6 packets, it's 0 for other
+/// packets. IPv4 fragments hash only their addresses, so all fragments of a packet reach the same socket.
+pub const INDEX_FLOW_HASH: u32 = 1;
+
+/// Configuration of the `redirect_sock` program, stored at index 0 of its `CONFIG` array map.
+///
+/// The all-zero default redirects packets to the socket at the index of their queue id and passes all other packets.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RedirectConfig {
+    /// One of the `FALLBACK_*` constants, for packets without a socket bound to their queue at their index.
+    pub fallback: u32,
+    /// One of the `INDEX_*` constants.
+    pub index: u32,
+    /// Sockets per queue for [`INDEX_FLOW_HASH`], 0 is treated as 1.
+    pub sockets_per_queue: u32,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for RedirectConfig {}
@@ -1,24 +1,54 @@
 #![no_std]
 #![no_main]
 
-use af_xdp_test_common::SOCKS_MAP_SIZE;
+use af_xdp_test_common::{
+    FALLBACK_ABORT, FALLBACK_DROP, INDEX_FLOW_HASH, RedirectConfig, SOCKS_MAP_SIZE,
+};
 use aya_ebpf::{
     bindings::xdp_action,
     macros::{map, xdp},
-    maps::XskMap,
+    maps::{Array, XskMap},
     programs::XdpContext,
 };
 use aya_log_ebpf::info;
 
+const ETH_HDR_LEN: usize = 14;
+const ETH_P_IP: u16 = 0x0800;
+const ETH_P_IPV6: u16 = 0x86DD;
+const IPV4_MIN_IHL: u8 = 5;
+const IPV4_MF: u16 = 0x2000;
+const IPV4_OFFSET: u16 = 0x1fff;
+const IPV6_HDR_LEN: usize = 40;
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+
 #[map]
 static SOCKS: XskMap = XskMap::with_max_entries(SOCKS_MAP_SIZE, 0);
 
+#[map]
+static CONFIG: Array<RedirectConfig> = Array::with_max_entries(1, 0);
+
 #[xdp]
 pub fn redirect_sock(ctx: XdpContext) -> u32 {
     let queue_id = unsafe { *ctx.ctx }.rx_queue_index;
-    if SOCKS.get(queue_id) == Some(queue_id) {
-        info!(&ctx, "Queue match on queue: {}", queue_id);
-        match SOCKS.redirect(queue_id, 0) {
+    let config = CONFIG.get(0).copied().unwrap_or_default();
+    let index = match config.index {
+        INDEX_FLOW_HASH => {
+            let sockets_per_queue = config.sockets_per_queue.max(1);
+            let flow_hash = flow_hash(&ctx).unwrap_or(0);
+            queue_id
+                .wrapping_mul(sockets_per_queue)
+                .wrapping_add(flow_hash % sockets_per_queue)
+        }
+        _ => queue_id,
+    };
+
+    if SOCKS.get(index) == Some(queue_id) {
+        info!(
+            &ctx,
+            "Queue match on queue: {} at index {}", queue_id, index
+        );
+        match SOCKS.redirect(index, 0) {
             Ok(ok_value) => {
                 info!(&ctx, "ok_value: {}", ok_value);
                 ok_value
@@ -31,10 +61,75 @@ pub fn redirect_sock(ctx: XdpContext) -> u32 {
     } else {
         info!(
             &ctx,
-            "No socket for queue {} at index {} in the XSKMAP", queue_id, queue_id
+            "No socket for queue {} at index {} in the XSKMAP", queue_id, index
         );
-        xdp_action::XDP_PASS
+        match config.fallback {
+            FALLBACK_DROP => xdp_action::XDP_DROP,
+            FALLBACK_ABORT => xdp_action::XDP_ABORTED,
+            _ => xdp_action::XDP_PASS,
+        }
+    }
+}
+
+/// Hashes the addresses, protocol and TCP or UDP ports of IPv4 and IPv6 packets, only the addresses of IPv4
+/// fragments.
+#[inline(always)]
+fn flow_hash(ctx: &XdpContext) -> Option<u32> {
+    let ether_type = u16::from_be(read_at::<u16>(ctx, 12)?);
+    let (protocol, l4_offset, hash) = match ether_type {
+        ETH_P_IP => {
+            let ihl = read_at::<u8>(ctx, ETH_HDR_LEN)? & 0x0f;
+            if ihl < IPV4_MIN_IHL {
+                return None;
+            }
+            let addresses = read_at::<[u32; 2]>(ctx, ETH_HDR_LEN + 12)?;
+            let hash = addresses.into_iter().fold(0, mix);
+            // Only the first fragment has the ports, all fragments of a packet hash by their addresses to reach the
+            // same socket.
+            let fragment = u16::from_be(read_at::<u16>(ctx, ETH_HDR_LEN + 6)?);
+            if fragment & (IPV4_MF | IPV4_OFFSET) != 0 {
+                return Some(finish(hash));
+            }
+            let protocol = read_at::<u8>(ctx, ETH_HDR_LEN + 9)?;
+            (protocol, ETH_HDR_LEN + usize::from(ihl) * 4, hash)
+        }
+        ETH_P_IPV6 => {
+            // Extension headers aren't followed, their packets hash without ports.
+            let protocol = read_at::<u8>(ctx, ETH_HDR_LEN + 6)?;
+            let addresses = read_at::<[u32; 8]>(ctx, ETH_HDR_LEN + 8)?;
+            let hash = addresses.into_iter().fold(0, mix);
+            (protocol, ETH_HDR_LEN + IPV6_HDR_LEN, hash)
+        }
+        _ => return None,
+    };
+
+    let mut hash = mix(hash, u32::from(protocol));
+    if protocol == IPPROTO_TCP || protocol == IPPROTO_UDP {
+        // Source and destination port.
+        hash = mix(hash, read_at::<u32>(ctx, l4_offset)?);
+    }
+    Some(finish(hash))
+}
+
+#[inline(always)]
+fn finish(hash: u32) -> u32 {
+    hash ^ (hash >> 16)
+}
+
+#[inline(always)]
+fn mix(hash: u32, word: u32) -> u32 {
+    (hash ^ word).wrapping_mul(0x9e37_79b1).rotate_left(15)
+}
+
+/// Reads a `T` at `offset` into the packet, the bounds check is required by the verifier.
+#[inline(always)]
+fn read_at<T: Copy>(ctx: &XdpContext, offset: usize) -> Option<T> {
+    let start = ctx.data();
+    let end = ctx.data_end();
+    if start + offset + size_of::<T>() > end {
+        return None;
     }
+    Some(unsafe { core::ptr::read_unaligned((start + offset) as *const T) })
 }
 
 #[panic_handler]
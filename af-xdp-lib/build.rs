@@ -0,0 +1,25 @@
+use anyhow::{Context as _, anyhow};
+use aya_build::{Package, Toolchain};
+use std::path::Path;
+
+// Builds the XDP program of af-xdp-ebpf into `OUT_DIR` for the `program` feature, this requires a nightly toolchain
+// and `bpf-linker`.
+fn main() -> anyhow::Result<()> {
+    if std::env::var_os("CARGO_FEATURE_PROGRAM").is_none() {
+        return Ok(());
+    }
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").context("CARGO_MANIFEST_DIR")?;
+    let root_dir = Path::new(&manifest_dir).join("../af-xdp-ebpf");
+    let root_dir = root_dir
+        .to_str()
+        .ok_or_else(|| anyhow!("af-xdp-ebpf path isn't valid UTF-8"))?;
+    aya_build::build_ebpf(
+        [Package {
+            name: "af-xdp-ebpf",
+            root_dir,
+            ..Default::default()
+        }],
+        Toolchain::default(),
+    )
+}
@@ -11,3 +11,35 @@ impl Display for ExceedsChunkSize {
 }
 
 impl Error for ExceedsChunkSize {}
+
+#[derive(Debug)]
+pub struct InsufficientHeadroom;
+
+impl Display for InsufficientHeadroom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not enough room between chunk start and data for the TX metadata."
+        )
+    }
+}
+
+impl Error for InsufficientHeadroom {}
+
+#[derive(Debug)]
+pub struct NotEnoughFrames {
+    pub needed: usize,
+    pub available: usize,
+}
+
+impl Display for NotEnoughFrames {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Packet needs {} frames, only {} are available.",
+            self.needed, self.available
+        )
+    }
+}
+
+impl Error for NotEnoughFrames {}
@@ -1,18 +1,30 @@
 pub mod error;
+pub mod packet;
+pub mod tx_metadata;
 
-use crate::descriptor::error::ExceedsChunkSize;
-use crate::descriptor::sealed::SealedDescriptorImpl;
-use crate::umem::memory::UmemMemory;
+use crate::descriptor::error::{ExceedsChunkSize, InsufficientHeadroom};
+use crate::descriptor::sealed::{SealedChunk, SealedDescriptorImpl};
+use crate::descriptor::tx_metadata::{TxMetadata, XDP_TX_METADATA};
+use crate::umem::ChunkMode;
 use rustix::net::xdp::{XdpDesc, XdpDescOptions};
 use std::any::type_name;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::ptr::NonNull;
 
 pub(crate) mod sealed {
+    use crate::umem::ChunkMode;
     use crate::umem::memory::UmemMemory;
+    use std::ptr::NonNull;
 
-    pub trait SealedDescriptorImpl<'umem, Marker, const CHUNK_SIZE: usize> {
+    pub trait SealedChunk {
+        /// The chunk of `chunk_size` bytes starting at `chunk`.
+        fn from_raw(chunk: NonNull<u8>, chunk_size: usize) -> NonNull<Self>;
+    }
+
+    pub trait SealedDescriptorImpl<'umem, Marker> {
         type InRingDescriptorType: Copy;
+        type Chunk: super::Chunk + ?Sized + 'umem;
 
         fn into_ring_repr(self) -> Self::InRingDescriptorType;
 
@@ -21,90 +33,131 @@ pub(crate) mod sealed {
         where
             Self: Sized,
         {
-            let offset = Self::base_addr(&ring_repr);
-            let memory = unsafe { memory.memory().byte_add(offset as usize).cast().as_mut() };
-            Self::from_desc(ring_repr, memory)
+            let chunk_mode = memory.chunk_mode();
+            let chunk_size = memory.chunk_size();
+            let offset = chunk_mode.chunk_addr(Self::addr(&ring_repr), chunk_size);
+            let chunk = unsafe { memory.memory().byte_add(offset as usize) };
+            let memory = unsafe { Self::Chunk::from_raw(chunk, chunk_size).as_mut() };
+            Self::from_desc(ring_repr, memory, chunk_mode)
         }
 
         fn from_desc(
             ring_repr: Self::InRingDescriptorType,
-            memory: &'umem mut [u8; CHUNK_SIZE],
+            memory: &'umem mut Self::Chunk,
+            chunk_mode: ChunkMode,
         ) -> Self;
 
-        fn base_addr(desc: &Self::InRingDescriptorType) -> u64;
+        fn addr(desc: &Self::InRingDescriptorType) -> u64;
+
+        /// Start of the chunk the descriptor points to.
+        fn chunk(&self) -> *const u8;
     }
 }
 
-pub trait Descriptor<'umem, Marker, const CHUNK_SIZE: usize>:
-    SealedDescriptorImpl<'umem, Marker, CHUNK_SIZE>
-{
+/// Memory of a chunk, `[u8; CHUNK_SIZE]` for a [`Umem`] and `[u8]` for a [`DynUmem`].
+///
+/// [`Umem`]: crate::umem::Umem
+/// [`DynUmem`]: crate::dynamic::umem::DynUmem
+pub trait Chunk: SealedChunk + AsRef<[u8]> + AsMut<[u8]> + Debug {}
+
+impl<const CHUNK_SIZE: usize> Chunk for [u8; CHUNK_SIZE] {}
+
+impl<const CHUNK_SIZE: usize> SealedChunk for [u8; CHUNK_SIZE] {
+    fn from_raw(chunk: NonNull<u8>, _chunk_size: usize) -> NonNull<Self> {
+        chunk.cast()
+    }
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> Descriptor<'umem, Marker, CHUNK_SIZE>
-    for RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl Chunk for [u8] {}
+
+impl SealedChunk for [u8] {
+    fn from_raw(chunk: NonNull<u8>, chunk_size: usize) -> NonNull<Self> {
+        NonNull::slice_from_raw_parts(chunk, chunk_size)
+    }
+}
+
+pub trait Descriptor<'umem, Marker>: SealedDescriptorImpl<'umem, Marker> {}
+
+impl<'umem, Marker, C> Descriptor<'umem, Marker> for RxTxDescriptor<'umem, Marker, C> where
+    C: Chunk + ?Sized
 {
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> SealedDescriptorImpl<'umem, Marker, CHUNK_SIZE>
-    for RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl<'umem, Marker, C> SealedDescriptorImpl<'umem, Marker> for RxTxDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
     type InRingDescriptorType = XdpDesc;
+    type Chunk = C;
 
     fn into_ring_repr(self) -> Self::InRingDescriptorType {
         self.descriptor
     }
 
-    #[expect(private_interfaces)]
-    fn from_ring_repr(ring_repr: Self::InRingDescriptorType, memory: &'umem UmemMemory) -> Self {
-        let offset = ring_repr.addr & !(CHUNK_SIZE as u64 - 1);
-
-        let memory = unsafe { memory.memory().byte_add(offset as usize).cast().as_mut() };
-        Self {
-            descriptor: ring_repr,
-            memory,
-            marker: PhantomData,
-        }
-    }
-
     fn from_desc(
         ring_repr: Self::InRingDescriptorType,
-        memory: &'umem mut [u8; CHUNK_SIZE],
+        memory: &'umem mut C,
+        chunk_mode: ChunkMode,
     ) -> Self {
         Self {
             descriptor: ring_repr,
             memory,
+            chunk_mode,
             marker: PhantomData,
         }
     }
 
-    fn base_addr(desc: &Self::InRingDescriptorType) -> u64 {
-        desc.addr & !(CHUNK_SIZE as u64 - 1)
+    fn addr(desc: &Self::InRingDescriptorType) -> u64 {
+        desc.addr
+    }
+    fn chunk(&self) -> *const u8 {
+        self.memory.as_ref().as_ptr()
     }
 }
 
-pub struct RxTxFrameDescriptor<'umem, Marker, const CHUNK_SIZE: usize> {
+/// An RX or TX descriptor, generic over the memory of its chunk.
+///
+/// See [`RxTxFrameDescriptor`] for the descriptors of a [`Umem`] and [`DynRxTxFrameDescriptor`] for the ones of a
+/// [`DynUmem`].
+///
+/// [`Umem`]: crate::umem::Umem
+/// [`DynUmem`]: crate::dynamic::umem::DynUmem
+/// [`DynRxTxFrameDescriptor`]: crate::dynamic::descriptor::DynRxTxFrameDescriptor
+pub struct RxTxDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
+{
     descriptor: XdpDesc,
-    memory: &'umem mut [u8; CHUNK_SIZE],
+    memory: &'umem mut C,
+    chunk_mode: ChunkMode,
     marker: PhantomData<fn(Marker)>,
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> Debug
-    for RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+pub type RxTxFrameDescriptor<'umem, Marker, const CHUNK_SIZE: usize> =
+    RxTxDescriptor<'umem, Marker, [u8; CHUNK_SIZE]>;
+
+impl<'umem, Marker, C> Debug for RxTxDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(&format!("RxTxFrameDescriptor<{}>", type_name::<Marker>()))
             .field("descriptor", &self.descriptor)
             .field("memory", &self.memory)
+            .field("chunk_mode", &self.chunk_mode)
             .finish()
     }
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE> {
-    pub fn memory(&self) -> &[u8; CHUNK_SIZE] {
+impl<'umem, Marker, C> RxTxDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
+{
+    pub fn memory(&self) -> &C {
         self.memory
     }
 
-    pub fn memory_mut(&mut self) -> &mut [u8; CHUNK_SIZE] {
+    pub fn memory_mut(&mut self) -> &mut C {
         self.memory
     }
 
@@ -117,9 +170,8 @@ impl<'umem, Marker, const CHUNK_SIZE: usize> RxTxFrameDescriptor<'umem, Marker,
     }
 
     pub fn data_offset(&self) -> usize {
-        (self.descriptor.addr
-            - <Self as SealedDescriptorImpl<Marker, CHUNK_SIZE>>::base_addr(&self.descriptor))
-            as usize
+        self.chunk_mode
+            .data_offset(self.descriptor.addr, self.chunk_size()) as usize
     }
 
     pub fn length(&self) -> usize {
@@ -139,47 +191,110 @@ impl<'umem, Marker, const CHUNK_SIZE: usize> RxTxFrameDescriptor<'umem, Marker,
         offset_from_base_addr: usize,
         length: u32,
     ) -> Result<(), ExceedsChunkSize> {
-        if offset_from_base_addr + length as usize > CHUNK_SIZE {
+        if offset_from_base_addr + length as usize > self.chunk_size() {
             return Err(ExceedsChunkSize);
         }
-        self.descriptor.addr =
-            <Self as SealedDescriptorImpl<Marker, CHUNK_SIZE>>::base_addr(&self.descriptor)
-                + offset_from_base_addr as u64;
+        self.descriptor.addr = self
+            .chunk_mode
+            .addr(self.chunk_addr(), offset_from_base_addr as u64);
         self.descriptor.len = length;
         Ok(())
     }
+
+    /// Returns the TX metadata in front of the data, `None` if it doesn't fit between the chunk start and the data.
+    pub fn tx_metadata(&self) -> Option<TxMetadata> {
+        read_tx_metadata(self.memory.as_ref(), self.data_offset())
+    }
+
+    /// Writes `metadata` in front of the data and marks the descriptor to carry it (`XDP_TX_METADATA`).
+    ///
+    /// The UMEM has to be built with [`UmemBuilder::tx_metadata`], the kernel drops frames carrying metadata
+    /// otherwise.
+    ///
+    /// [`UmemBuilder::tx_metadata`]: crate::umem::UmemBuilder::tx_metadata
+    pub fn set_tx_metadata(&mut self, metadata: TxMetadata) -> Result<(), InsufficientHeadroom> {
+        let metadata_offset = self
+            .data_offset()
+            .checked_sub(size_of::<TxMetadata>())
+            .ok_or(InsufficientHeadroom)?;
+        // The metadata isn't necessarily aligned within the chunk.
+        unsafe {
+            self.memory
+                .as_mut()
+                .as_mut_ptr()
+                .add(metadata_offset)
+                .cast::<TxMetadata>()
+                .write_unaligned(metadata)
+        };
+        self.descriptor.options |= XDP_TX_METADATA;
+        Ok(())
+    }
+
+    /// Marks the descriptor to not carry TX metadata, the memory in front of the data is left as is.
+    pub fn clear_tx_metadata(&mut self) {
+        self.descriptor.options.remove(XDP_TX_METADATA);
+    }
+
+    fn chunk_size(&self) -> usize {
+        self.memory.as_ref().len()
+    }
+
+    fn chunk_addr(&self) -> u64 {
+        self.chunk_mode
+            .chunk_addr(self.descriptor.addr, self.chunk_size())
+    }
+}
+
+pub(crate) fn read_tx_metadata(memory: &[u8], data_offset: usize) -> Option<TxMetadata> {
+    let metadata_offset = data_offset.checked_sub(size_of::<TxMetadata>())?;
+    // Any bit pattern is a valid `TxMetadata`, which isn't necessarily aligned within the chunk.
+    Some(unsafe {
+        memory
+            .as_ptr()
+            .add(metadata_offset)
+            .cast::<TxMetadata>()
+            .read_unaligned()
+    })
 }
-impl<'umem, Marker, const CHUNK_SIZE: usize>
-    From<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>
-    for RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl<'umem, Marker, C> From<FillCompDescriptor<'umem, Marker, C>>
+    for RxTxDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
-    fn from(value: FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>) -> Self {
+    fn from(value: FillCompDescriptor<'umem, Marker, C>) -> Self {
         let xdp_desc = XdpDesc {
             addr: value.addr,
             len: 0,
             options: XdpDescOptions::empty(),
         };
-        RxTxFrameDescriptor::from_desc(xdp_desc, value.memory)
+        RxTxDescriptor::from_desc(xdp_desc, value.memory, value.chunk_mode)
     }
 }
 
-unsafe impl<'umem, Marker, const CHUNK_SIZE: usize> Send
-    for RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+/// A fill or completion descriptor, generic over the memory of its chunk.
+///
+/// See [`FillCompFrameDescriptor`] for the descriptors of a [`Umem`] and [`DynFillCompFrameDescriptor`] for the ones
+/// of a [`DynUmem`].
+///
+/// [`Umem`]: crate::umem::Umem
+/// [`DynUmem`]: crate::dynamic::umem::DynUmem
+/// [`DynFillCompFrameDescriptor`]: crate::dynamic::descriptor::DynFillCompFrameDescriptor
+pub struct FillCompDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
-}
-unsafe impl<'umem, Marker, const CHUNK_SIZE: usize> Sync
-    for RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>
-{
-}
-
-pub struct FillCompFrameDescriptor<'umem, Marker, const CHUNK_SIZE: usize> {
     addr: u64,
-    memory: &'umem mut [u8; CHUNK_SIZE],
-    marker: PhantomData<Marker>,
+    memory: &'umem mut C,
+    chunk_mode: ChunkMode,
+    marker: PhantomData<fn(Marker)>,
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> Debug
-    for FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+pub type FillCompFrameDescriptor<'umem, Marker, const CHUNK_SIZE: usize> =
+    FillCompDescriptor<'umem, Marker, [u8; CHUNK_SIZE]>;
+
+impl<'umem, Marker, C> Debug for FillCompDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(&format!(
@@ -188,54 +303,73 @@ impl<'umem, Marker, const CHUNK_SIZE: usize> Debug
         ))
         .field("addr", &self.addr)
         .field("memory", &self.memory)
+        .field("chunk_mode", &self.chunk_mode)
         .finish()
     }
 }
 
-unsafe impl<'umem, Marker, const CHUNK_SIZE: usize> Send
-    for FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>
-{
-}
-
-unsafe impl<'umem, Marker, const CHUNK_SIZE: usize> Sync
-    for FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl<'umem, Marker, C> FillCompDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
+    /// Returns the TX metadata in front of the data of a completed TX frame, e.g. to get its TX timestamp.
+    ///
+    /// `None` if the metadata doesn't fit between the chunk start and the data.
+    pub fn tx_metadata(&self) -> Option<TxMetadata> {
+        let memory = self.memory.as_ref();
+        read_tx_metadata(
+            memory,
+            self.chunk_mode.data_offset(self.addr, memory.len()) as usize,
+        )
+    }
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> Descriptor<'umem, Marker, CHUNK_SIZE>
-    for FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl<'umem, Marker, C> Descriptor<'umem, Marker> for FillCompDescriptor<'umem, Marker, C> where
+    C: Chunk + ?Sized
 {
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> SealedDescriptorImpl<'umem, Marker, CHUNK_SIZE>
-    for FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl<'umem, Marker, C> SealedDescriptorImpl<'umem, Marker> for FillCompDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
     type InRingDescriptorType = u64;
+    type Chunk = C;
 
     fn into_ring_repr(self) -> Self::InRingDescriptorType {
-        self.addr
+        // Completed TX descriptors may point into the chunk. In unaligned mode, the kernel would use that offset
+        // as the start of the chunk when handing it out through the fill ring.
+        self.chunk_mode
+            .chunk_addr(self.addr, self.memory.as_ref().len())
     }
 
     fn from_desc(
         ring_repr: Self::InRingDescriptorType,
-        memory: &'umem mut [u8; CHUNK_SIZE],
+        memory: &'umem mut C,
+        chunk_mode: ChunkMode,
     ) -> Self {
         Self {
             addr: ring_repr,
             memory,
+            chunk_mode,
             marker: PhantomData,
         }
     }
 
-    fn base_addr(desc: &Self::InRingDescriptorType) -> u64 {
-        desc & !(CHUNK_SIZE as u64 - 1)
+    fn addr(desc: &Self::InRingDescriptorType) -> u64 {
+        *desc
+    }
+    fn chunk(&self) -> *const u8 {
+        self.memory.as_ref().as_ptr()
     }
 }
 
-impl<'umem, Marker, const CHUNK_SIZE: usize> From<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>
-    for FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>
+impl<'umem, Marker, C> From<RxTxDescriptor<'umem, Marker, C>>
+    for FillCompDescriptor<'umem, Marker, C>
+where
+    C: Chunk + ?Sized,
 {
-    fn from(value: RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>) -> Self {
-        FillCompFrameDescriptor::from_desc(<RxTxFrameDescriptor<Marker, CHUNK_SIZE> as SealedDescriptorImpl<Marker, CHUNK_SIZE>>::base_addr(&value.descriptor), value.memory)
+    fn from(value: RxTxDescriptor<'umem, Marker, C>) -> Self {
+        FillCompDescriptor::from_desc(value.chunk_addr(), value.memory, value.chunk_mode)
     }
 }
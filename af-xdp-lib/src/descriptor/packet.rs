@@ -0,0 +1,109 @@
+use crate::descriptor::error::NotEnoughFrames;
+use crate::descriptor::{FillCompFrameDescriptor, RxTxFrameDescriptor};
+use rustix::net::xdp::XdpDescOptions;
+use std::any::type_name;
+use std::fmt::Debug;
+
+/// A packet spanning one or more frames, chained by `XDP_PKT_CONTD` on every frame but the last.
+///
+/// Packets larger than a chunk require a socket bound with
+/// [`SocketBuilder::multi_buffer`](crate::socket::SocketBuilder::multi_buffer). The kernel limits the number of
+/// frames per packet, to `MAX_SKB_FRAGS + 1` in copy mode and to a driver specific value in zero-copy mode.
+pub struct Packet<'umem, Marker, const CHUNK_SIZE: usize> {
+    frames: Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+}
+
+impl<'umem, Marker, const CHUNK_SIZE: usize> Debug for Packet<'umem, Marker, CHUNK_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("Packet<{}>", type_name::<Marker>()))
+            .field("frames", &self.frames)
+            .finish()
+    }
+}
+
+impl<'umem, Marker, const CHUNK_SIZE: usize> Packet<'umem, Marker, CHUNK_SIZE> {
+    /// Chains `frames` into a packet by setting `XDP_PKT_CONTD` on every frame but the last.
+    pub fn new(mut frames: Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>) -> Self {
+        let last = frames.len().saturating_sub(1);
+        for (index, frame) in frames.iter_mut().enumerate() {
+            let mut options = frame.options();
+            options.set(XdpDescOptions::XDP_PKT_CONTD, index != last);
+            frame.set_options(options);
+        }
+        Self { frames }
+    }
+
+    /// Copies `data` into frames taken from the end of `descriptors`, starting at `data_offset` in each chunk.
+    ///
+    /// `descriptors` is left untouched if it doesn't hold enough frames for `data`.
+    ///
+    /// # Panics
+    ///
+    /// If `data_offset` doesn't leave room for data in the chunk.
+    pub fn from_slice(
+        data: &[u8],
+        data_offset: usize,
+        descriptors: &mut Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> Result<Self, NotEnoughFrames> {
+        assert!(
+            data_offset < CHUNK_SIZE,
+            "data offset {data_offset} leaves no room for data in a chunk of {CHUNK_SIZE} bytes"
+        );
+        let frame_data_length = CHUNK_SIZE - data_offset;
+        let needed = data.len().div_ceil(frame_data_length).max(1);
+        if needed > descriptors.len() {
+            return Err(NotEnoughFrames {
+                needed,
+                available: descriptors.len(),
+            });
+        }
+
+        let mut chunks = data.chunks(frame_data_length);
+        let frames = descriptors
+            .drain(descriptors.len() - needed..)
+            .rev()
+            .map(|descriptor| {
+                let mut frame = RxTxFrameDescriptor::from(descriptor);
+                let chunk = chunks.next().unwrap_or_default();
+                frame
+                    .set_addr_and_length(data_offset, chunk.len() as u32)
+                    .expect("chunk was split to fit behind the data offset");
+                frame.memory_mut()[data_offset..][..chunk.len()].copy_from_slice(chunk);
+                frame
+            })
+            .collect();
+        Ok(Self::new(frames))
+    }
+
+    pub fn frames(&self) -> &[RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>] {
+        &self.frames
+    }
+
+    pub fn into_frames(self) -> Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>> {
+        self.frames
+    }
+
+    /// Length of the packet's data across all frames.
+    pub fn len(&self) -> usize {
+        self.frames.iter().map(|frame| frame.length()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the data of each frame in order.
+    pub fn data(&self) -> impl Iterator<Item = &[u8]> {
+        self.frames
+            .iter()
+            .map(|frame| &frame.memory()[frame.data_offset()..][..frame.length()])
+    }
+
+    /// Copies the data of all frames into a contiguous buffer.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len());
+        self.data()
+            .for_each(|frame_data| data.extend_from_slice(frame_data));
+        data
+    }
+}
@@ -0,0 +1,128 @@
+use rustix::net::xdp::{XdpDescOptions, XdpUmemRegFlags};
+use std::fmt::{Debug, Formatter};
+
+// rustix doesn't expose the TX metadata constants yet.
+/// `XDP_TX_METADATA`, the descriptor carries [`TxMetadata`] in front of the data.
+pub(crate) const XDP_TX_METADATA: XdpDescOptions = XdpDescOptions::from_bits_retain(1 << 1);
+/// `XDP_UMEM_TX_METADATA_LEN`, the `tx_metadata_len` field of the UMEM registration is valid.
+pub(crate) const XDP_UMEM_TX_METADATA_LEN: XdpUmemRegFlags =
+    XdpUmemRegFlags::from_bits_retain(1 << 2);
+
+/// `XDP_TXMD_FLAGS_TIMESTAMP`
+const XDP_TXMD_FLAGS_TIMESTAMP: u64 = 1 << 0;
+/// `XDP_TXMD_FLAGS_CHECKSUM`
+const XDP_TXMD_FLAGS_CHECKSUM: u64 = 1 << 1;
+/// `XDP_TXMD_FLAGS_LAUNCH_TIME`
+const XDP_TXMD_FLAGS_LAUNCH_TIME: u64 = 1 << 2;
+
+/// Metadata the kernel reads in front of the data of a TX frame (`struct xsk_tx_metadata`).
+///
+/// The requested offloads are only applied if the driver supports them. The kernel overwrites the request with the
+/// completion once the frame was sent, see [`tx_timestamp`](TxMetadata::tx_timestamp).
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct TxMetadata {
+    flags: u64,
+    request_or_completion: RequestOrCompletion,
+}
+
+// The kernel requires the metadata length to be a multiple of 8.
+const _: () = assert!(size_of::<TxMetadata>() == 24);
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+union RequestOrCompletion {
+    request: Request,
+    completion: Completion,
+}
+
+impl Default for RequestOrCompletion {
+    fn default() -> Self {
+        Self {
+            request: Request::default(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+struct Request {
+    csum_start: u16,
+    csum_offset: u16,
+    // Explicit, so the bytes overlapping `Completion::tx_timestamp` are always initialized.
+    _padding: u32,
+    launch_time: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+struct Completion {
+    tx_timestamp: u64,
+}
+
+impl Debug for TxMetadata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TxMetadata")
+            .field("flags", &self.flags)
+            .field("checksum", &self.checksum())
+            .field("launch_time", &self.launch_time())
+            .finish()
+    }
+}
+
+impl TxMetadata {
+    /// Requests the TX timestamp, which the kernel writes into the metadata on completion.
+    pub fn request_timestamp(mut self) -> Self {
+        self.flags |= XDP_TXMD_FLAGS_TIMESTAMP;
+        self
+    }
+
+    /// Requests L4 checksum offload.
+    ///
+    /// `csum_start` is the offset of the start of the checksummed data from the start of the frame, `csum_offset` the
+    /// offset of the checksum field from `csum_start`.
+    pub fn request_checksum(mut self, csum_start: u16, csum_offset: u16) -> Self {
+        self.flags |= XDP_TXMD_FLAGS_CHECKSUM;
+        // Both union variants only consist of integers, any bit pattern is valid.
+        let request = unsafe { &mut self.request_or_completion.request };
+        request.csum_start = csum_start;
+        request.csum_offset = csum_offset;
+        self
+    }
+
+    /// Requests the frame to be sent at `launch_time`, in nanoseconds of the clock the device's qdisc uses.
+    pub fn request_launch_time(mut self, launch_time: u64) -> Self {
+        self.flags |= XDP_TXMD_FLAGS_LAUNCH_TIME;
+        let request = unsafe { &mut self.request_or_completion.request };
+        request.launch_time = launch_time;
+        self
+    }
+
+    /// Returns `csum_start` and `csum_offset` if checksum offload was requested.
+    pub fn checksum(&self) -> Option<(u16, u16)> {
+        if self.flags & XDP_TXMD_FLAGS_CHECKSUM == 0 {
+            return None;
+        }
+        let request = unsafe { self.request_or_completion.request };
+        Some((request.csum_start, request.csum_offset))
+    }
+
+    /// Returns the launch time if launch-time scheduling was requested.
+    pub fn launch_time(&self) -> Option<u64> {
+        if self.flags & XDP_TXMD_FLAGS_LAUNCH_TIME == 0 {
+            return None;
+        }
+        Some(unsafe { self.request_or_completion.request }.launch_time)
+    }
+
+    /// Returns the TX timestamp if it was requested.
+    ///
+    /// Only meaningful once the frame was returned through the completion ring and if the driver supports TX
+    /// timestamps. Before that, the value overlaps the request.
+    pub fn tx_timestamp(&self) -> Option<u64> {
+        if self.flags & XDP_TXMD_FLAGS_TIMESTAMP == 0 {
+            return None;
+        }
+        Some(unsafe { self.request_or_completion.completion }.tx_timestamp)
+    }
+}
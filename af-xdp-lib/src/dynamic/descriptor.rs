@@ -0,0 +1,12 @@
+use crate::descriptor::{FillCompDescriptor, RxTxDescriptor};
+
+/// An [`RxTxFrameDescriptor`](crate::descriptor::RxTxFrameDescriptor) of a [`DynUmem`], its chunk size is the
+/// length of [`memory`](RxTxDescriptor::memory).
+///
+/// [`DynUmem`]: crate::dynamic::umem::DynUmem
+pub type DynRxTxFrameDescriptor<'umem, Marker> = RxTxDescriptor<'umem, Marker, [u8]>;
+
+/// A [`FillCompFrameDescriptor`](crate::descriptor::FillCompFrameDescriptor) of a [`DynUmem`].
+///
+/// [`DynUmem`]: crate::dynamic::umem::DynUmem
+pub type DynFillCompFrameDescriptor<'umem, Marker> = FillCompDescriptor<'umem, Marker, [u8]>;
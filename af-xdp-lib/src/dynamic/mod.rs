@@ -0,0 +1,4 @@
+pub mod descriptor;
+pub mod ring;
+pub mod umem;
+pub mod xsk_map;
@@ -0,0 +1,79 @@
+use crate::dynamic::descriptor::{DynFillCompFrameDescriptor, DynRxTxFrameDescriptor};
+use crate::dynamic::umem::DynUmem;
+use crate::error::Error;
+use crate::ring::{Consumer, DynSize, Producer, Ring, RingKind};
+use std::os::fd::OwnedFd;
+use std::sync::Arc;
+
+pub type DynRxRing<'umem, Marker> =
+    Ring<'umem, Consumer, DynRxTxFrameDescriptor<'umem, Marker>, Marker, DynSize>;
+pub type DynTxRing<'umem, Marker> =
+    Ring<'umem, Producer, DynRxTxFrameDescriptor<'umem, Marker>, Marker, DynSize>;
+pub type DynCompletionRing<'umem, Marker> =
+    Ring<'umem, Consumer, DynFillCompFrameDescriptor<'umem, Marker>, Marker, DynSize>;
+pub type DynFillRing<'umem, Marker> =
+    Ring<'umem, Producer, DynFillCompFrameDescriptor<'umem, Marker>, Marker, DynSize>;
+
+impl<'umem, Marker> DynRxRing<'umem, Marker> {
+    pub(crate) fn new(
+        umem: &'umem DynUmem<Marker>,
+        socket: Arc<OwnedFd>,
+        size: u32,
+    ) -> Result<Self, Error> {
+        DynRxRing::internal_new(
+            RingKind::Rx,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            DynSize(size),
+        )
+    }
+}
+
+impl<'umem, Marker> DynTxRing<'umem, Marker> {
+    pub(crate) fn new(
+        umem: &'umem DynUmem<Marker>,
+        socket: Arc<OwnedFd>,
+        size: u32,
+    ) -> Result<Self, Error> {
+        DynTxRing::internal_new(
+            RingKind::Tx,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            DynSize(size),
+        )
+    }
+}
+
+impl<'umem, Marker> DynCompletionRing<'umem, Marker> {
+    pub(crate) fn new(
+        umem: &'umem DynUmem<Marker>,
+        socket: Arc<OwnedFd>,
+        size: u32,
+    ) -> Result<Self, Error> {
+        DynCompletionRing::internal_new(
+            RingKind::Completion,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            DynSize(size),
+        )
+    }
+}
+
+impl<'umem, Marker> DynFillRing<'umem, Marker> {
+    pub(crate) fn new(
+        umem: &'umem DynUmem<Marker>,
+        socket: Arc<OwnedFd>,
+        size: u32,
+    ) -> Result<Self, Error> {
+        DynFillRing::internal_new(
+            RingKind::Fill,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            DynSize(size),
+        )
+    }
+}
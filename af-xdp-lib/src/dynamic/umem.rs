@@ -0,0 +1,225 @@
+use crate::dynamic::descriptor::DynFillCompFrameDescriptor;
+use crate::error::Error;
+use crate::umem::maker_guard::{MarkerGuard, is_runtime_checked};
+use crate::umem::memory::UmemMemory;
+use crate::umem::region::UmemRegion;
+use crate::umem::registration::{UmemConfig, UmemSocket};
+use crate::umem::{Brand, ChunkMode, DescriptorsToken, MemoryBacking};
+use std::any::type_name;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+/// Smallest chunk size the kernel accepts (`XDP_UMEM_MIN_CHUNK_SIZE`).
+pub const MIN_CHUNK_SIZE: usize = 2048;
+
+/// A [`Umem`](crate::umem::Umem) whose chunk size is only known at runtime, e.g. from a command line argument.
+///
+/// The chunk size is checked when building the UMEM instead of at compile time, the kernel rejects chunks larger
+/// than a page unless they're backed by hugepages. Its rings are [`Ring`](crate::ring::Ring)s with a runtime
+/// size, the const generic UMEM and rings stay the zero-overhead path and additionally offer multi-buffer packets,
+/// the async rings, frame pools and drivers.
+pub struct DynUmem<Marker> {
+    // Declared before `memory` to close the socket the memory is registered with before the memory is released.
+    socket: UmemSocket,
+    memory: UmemMemory,
+    marker_guard: MarkerGuard,
+    marker: PhantomData<fn(Marker)>,
+}
+
+impl<Marker> Debug for DynUmem<Marker> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("DynUmem<{}>", type_name::<Marker>()))
+            .field("memory", &self.memory)
+            .field(
+                "initial_rings_given_out",
+                &self.socket.initial_rings_given_out(),
+            )
+            .finish()
+    }
+}
+
+/// Builder for a [`DynUmem`].
+pub struct DynUmemBuilder<Marker> {
+    config: UmemConfig,
+    marker: PhantomData<fn(Marker)>,
+}
+
+impl<Marker> Debug for DynUmemBuilder<Marker> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.config
+            .debug(f, &format!("DynUmemBuilder<{}>", type_name::<Marker>()))
+    }
+}
+
+impl<Marker> DynUmemBuilder<Marker> {
+    /// Sets the headroom the kernel leaves free in front of received data, 0 by default.
+    pub fn headroom(mut self, headroom: u32) -> Self {
+        self.config.headroom = headroom;
+        self
+    }
+
+    /// Sets how the chunks are laid out, [`ChunkMode::Aligned`] by default.
+    pub fn chunk_mode(mut self, chunk_mode: ChunkMode) -> Self {
+        self.config.chunk_mode = chunk_mode;
+        self
+    }
+
+    /// Sets the pages the memory is allocated from, [`MemoryBacking::Regular`] by default.
+    ///
+    /// Falls back to regular pages if the requested hugepages aren't available, see [`DynUmem::memory_backing`]
+    /// for the backing actually used.
+    pub fn memory_backing(mut self, memory_backing: MemoryBacking) -> Self {
+        self.config.memory_backing = memory_backing;
+        self
+    }
+
+    /// Registers memory owned by the caller as UMEM instead of allocating it, overriding the memory backing.
+    ///
+    /// The region has to hold at least the chunks passed to [`build`](DynUmemBuilder::build).
+    pub fn memory_region(mut self, memory_region: impl UmemRegion + 'static) -> Self {
+        self.config.memory_region = Some(Box::new(memory_region));
+        self
+    }
+
+    /// Sets whether TX frames can carry TX metadata in front of their data, see
+    /// [`UmemBuilder::tx_metadata`](crate::umem::UmemBuilder::tx_metadata).
+    pub fn tx_metadata(mut self, tx_metadata: bool) -> Self {
+        self.config.tx_metadata = tx_metadata;
+        self
+    }
+
+    /// Allocates the memory for `number_of_chunks` chunks of `chunk_size` bytes and registers it as UMEM.
+    ///
+    /// `chunk_size` has to be at least [`MIN_CHUNK_SIZE`] and, in aligned chunk mode, a power of two.
+    pub fn build(
+        self,
+        chunk_size: usize,
+        number_of_chunks: usize,
+    ) -> Result<(DynUmem<Marker>, DescriptorsToken<Marker>), Error>
+    where
+        Marker: 'static,
+    {
+        check_chunk_size(&self.config, chunk_size)?;
+        let marker_guard = MarkerGuard::new::<Marker>()?;
+        DynUmem::register(
+            self.config,
+            chunk_size,
+            number_of_chunks,
+            marker_guard,
+            is_runtime_checked::<Marker>(),
+        )
+    }
+}
+
+impl DynUmemBuilder<Brand<'_>> {
+    /// Allocates the memory for `number_of_chunks` chunks of `chunk_size` bytes, registers it as UMEM and passes it
+    /// to `f`.
+    ///
+    /// See [`UmemBuilder::with`](crate::umem::UmemBuilder::with).
+    pub fn with<R>(
+        self,
+        chunk_size: usize,
+        number_of_chunks: usize,
+        f: impl for<'id> FnOnce(DynUmem<Brand<'id>>, DescriptorsToken<Brand<'id>>) -> R,
+    ) -> Result<R, Error> {
+        check_chunk_size(&self.config, chunk_size)?;
+        let (umem, descriptors_token) = DynUmem::register(
+            self.config,
+            chunk_size,
+            number_of_chunks,
+            MarkerGuard::branded(),
+            false,
+        )?;
+        Ok(f(umem, descriptors_token))
+    }
+}
+
+/// `chunk_size` has to be at least [`MIN_CHUNK_SIZE`] and, in aligned chunk mode, a power of two.
+fn check_chunk_size(config: &UmemConfig, chunk_size: usize) -> Result<(), Error> {
+    if chunk_size < MIN_CHUNK_SIZE
+        || u32::try_from(chunk_size).is_err()
+        || (config.chunk_mode == ChunkMode::Aligned && !chunk_size.is_power_of_two())
+    {
+        return Err(Error::InvalidChunkSize { chunk_size });
+    }
+    Ok(())
+}
+
+impl<Marker> DynUmem<Marker> {
+    pub fn new(
+        chunk_size: usize,
+        headroom: u32,
+        number_of_chunks: usize,
+    ) -> Result<(Self, DescriptorsToken<Marker>), Error>
+    where
+        Marker: 'static,
+    {
+        Self::builder()
+            .headroom(headroom)
+            .build(chunk_size, number_of_chunks)
+    }
+
+    pub fn builder() -> DynUmemBuilder<Marker> {
+        DynUmemBuilder {
+            config: UmemConfig::default(),
+            marker: PhantomData,
+        }
+    }
+
+    fn register(
+        config: UmemConfig,
+        chunk_size: usize,
+        number_of_chunks: usize,
+        marker_guard: MarkerGuard,
+        check_chunks: bool,
+    ) -> Result<(Self, DescriptorsToken<Marker>), Error> {
+        let (socket, mut memory) = config.register(chunk_size, number_of_chunks)?;
+        memory.set_check_chunks(check_chunks);
+
+        let descriptors_token = DescriptorsToken::new(&marker_guard);
+        let umem = DynUmem {
+            socket,
+            memory,
+            marker_guard,
+            marker: PhantomData,
+        };
+
+        Ok((umem, descriptors_token))
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.memory.chunk_size()
+    }
+
+    /// Returns how the chunks of the UMEM are laid out.
+    pub fn chunk_mode(&self) -> ChunkMode {
+        self.memory.chunk_mode()
+    }
+
+    /// Returns the pages actually backing the memory of the UMEM, `None` for memory provided through
+    /// [`DynUmemBuilder::memory_region`].
+    pub fn memory_backing(&self) -> Option<MemoryBacking> {
+        self.memory.backing()
+    }
+
+    /// Returns a descriptor for each chunk of the UMEM.
+    ///
+    /// # Panics
+    ///
+    /// If `token` was created with another UMEM.
+    pub fn descriptors(
+        &'_ self,
+        token: DescriptorsToken<Marker>,
+    ) -> Vec<DynFillCompFrameDescriptor<'_, Marker>> {
+        token.redeem(&self.marker_guard);
+        self.memory.descriptors()
+    }
+
+    pub(crate) fn socket(&self) -> &UmemSocket {
+        &self.socket
+    }
+
+    pub(crate) fn memory(&self) -> &UmemMemory {
+        &self.memory
+    }
+}
@@ -0,0 +1,341 @@
+use crate::dynamic::ring::{DynCompletionRing, DynFillRing, DynRxRing, DynTxRing};
+use crate::dynamic::umem::DynUmem;
+use crate::error::Error;
+use crate::socket::{BindMode, BusyPoll, FillComp, SocketOptions};
+use crate::umem::registration::{BindLock, QueueBinding};
+use crate::umem::{DeviceId, QueueId};
+use crate::xsk_map::{XskMap, XskMapEntry};
+use rustix::net::xdp::SocketAddrXdpFlags;
+use std::os::fd::{AsFd, OwnedFd};
+use std::sync::{Arc, Mutex};
+
+/// The number of entries of each ring of a socket, each has to be a power of two.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct RingSizes {
+    pub fill: u32,
+    pub completion: u32,
+    pub rx: u32,
+    pub tx: u32,
+}
+
+impl RingSizes {
+    /// The same size for all rings.
+    pub fn uniform(size: u32) -> Self {
+        Self {
+            fill: size,
+            completion: size,
+            rx: size,
+            tx: size,
+        }
+    }
+}
+
+/// An [`XskMapStorage`](crate::xsk_map::XskMapStorage) for the sockets of a [`DynUmem`].
+///
+/// Only covers sockets with an RX and TX ring, see [`DynRings`]. RX-only and TX-only sockets, multi-buffer packets,
+/// ring handles and the fill and completion ring driver are only available for the const generic UMEM.
+pub struct DynXskMapStorage<'umem, XM, Marker>
+where
+    XM: XskMap,
+{
+    xsk_map: Arc<Mutex<XM>>,
+    net_device_id: DeviceId,
+    umem: &'umem DynUmem<Marker>,
+}
+
+impl<'umem, XM, Marker> DynXskMapStorage<'umem, XM, Marker>
+where
+    XM: XskMap,
+{
+    pub fn new(xsk_map: XM, net_device_id: DeviceId, umem: &'umem DynUmem<Marker>) -> Self {
+        Self {
+            xsk_map: Arc::new(Mutex::new(xsk_map)),
+            net_device_id,
+            umem,
+        }
+    }
+
+    /// Returns the XSKMAP, fails if ring sets still register sockets in it.
+    pub fn into_inner(self) -> Result<XM, Self> {
+        match Arc::try_unwrap(self.xsk_map) {
+            Ok(xsk_map) => Ok(xsk_map.into_inner().unwrap()),
+            Err(xsk_map) => Err(Self { xsk_map, ..self }),
+        }
+    }
+
+    /// Sets up the rings with `ring_size` entries of a new socket bound to `queue_id` and registers it at
+    /// `map_index` in the XSKMAP.
+    ///
+    /// # Panics
+    ///
+    /// Panics if setting up the rings fails, see [`Self::try_rings`] for a fallible version.
+    pub fn rings(
+        &self,
+        queue_id: QueueId,
+        map_index: u32,
+        ring_size: u32,
+    ) -> DynRings<'umem, Marker, XM> {
+        self.try_rings(queue_id, map_index, ring_size)
+            .expect("failed to set up rings")
+    }
+
+    /// Sets up the rings with `ring_size` entries of a new socket bound to `queue_id` and registers it at
+    /// `map_index` in the XSKMAP.
+    ///
+    /// `ring_size` has to be a power of two. Everything set up before a failing step is released again before the
+    /// error is returned.
+    pub fn try_rings(
+        &self,
+        queue_id: QueueId,
+        map_index: u32,
+        ring_size: u32,
+    ) -> Result<DynRings<'umem, Marker, XM>, Error> {
+        self.socket_builder(queue_id, map_index).build(ring_size)
+    }
+
+    /// Returns a builder to configure the socket before setting up its rings.
+    pub fn socket_builder(
+        &self,
+        queue_id: QueueId,
+        map_index: u32,
+    ) -> DynSocketBuilder<'umem, '_, XM, Marker> {
+        DynSocketBuilder {
+            xsk_map: self,
+            queue_id,
+            map_index,
+            options: SocketOptions::default(),
+        }
+    }
+
+    // See `XskMapStorage` for the order of the steps.
+
+    fn fill_comp_rx_tx_rings(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        map_index: u32,
+        bind_flags: SocketAddrXdpFlags,
+        ring_sizes: RingSizes,
+    ) -> Result<DynFillCompRxTxRings<'umem, Marker, XM>, Error> {
+        let rx_ring = DynRxRing::new(self.umem, socket.clone(), ring_sizes.rx)?;
+        let tx_ring = DynTxRing::new(self.umem, socket.clone(), ring_sizes.tx)?;
+        let fill_ring = DynFillRing::new(self.umem, socket.clone(), ring_sizes.fill)?;
+        let completion_ring =
+            DynCompletionRing::new(self.umem, socket.clone(), ring_sizes.completion)?;
+
+        let xsk_map_entry = XskMapEntry::new(&self.xsk_map, map_index, socket.as_fd())?;
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, true)?;
+
+        Ok(DynFillCompRxTxRings {
+            _xsk_map_entry: xsk_map_entry,
+            fill_ring,
+            completion_ring,
+            rx_ring,
+            tx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+
+    fn rx_tx_rings(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        map_index: u32,
+        bind_flags: SocketAddrXdpFlags,
+        ring_sizes: RingSizes,
+    ) -> Result<DynRxTxRings<'umem, Marker, XM>, Error> {
+        let rx_ring = DynRxRing::new(self.umem, socket.clone(), ring_sizes.rx)?;
+        let tx_ring = DynTxRing::new(self.umem, socket.clone(), ring_sizes.tx)?;
+
+        let xsk_map_entry = XskMapEntry::new(&self.xsk_map, map_index, socket.as_fd())?;
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, false)?;
+
+        Ok(DynRxTxRings {
+            _xsk_map_entry: xsk_map_entry,
+            rx_ring,
+            tx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+}
+
+/// Builder for the dynamically sized rings of a socket, see [`SocketBuilder`](crate::socket::SocketBuilder).
+///
+/// The socket always has an RX and TX ring and doesn't support multi-buffer packets.
+pub struct DynSocketBuilder<'umem, 'xsk, XM, Marker>
+where
+    XM: XskMap,
+{
+    xsk_map: &'xsk DynXskMapStorage<'umem, XM, Marker>,
+    queue_id: QueueId,
+    map_index: u32,
+    options: SocketOptions,
+}
+
+impl<'umem, 'xsk, XM, Marker> DynSocketBuilder<'umem, 'xsk, XM, Marker>
+where
+    XM: XskMap,
+{
+    /// Sets how frames are moved between the driver and the UMEM, [`BindMode::Auto`] by default.
+    pub fn bind_mode(mut self, bind_mode: BindMode) -> Self {
+        self.options.bind_mode = bind_mode;
+        self
+    }
+
+    /// Sets whether the kernel signals through the ring flags that it needs a wakeup (`XDP_USE_NEED_WAKEUP`),
+    /// enabled by default.
+    pub fn need_wakeup(mut self, need_wakeup: bool) -> Self {
+        self.options.need_wakeup = need_wakeup;
+        self
+    }
+
+    /// Enables preferred busy polling, disabled by default.
+    pub fn busy_poll(mut self, busy_poll: BusyPoll) -> Self {
+        self.options.busy_poll = Some(busy_poll);
+        self
+    }
+
+    /// Sets whether the socket has its own fill and completion ring, [`FillComp::Auto`] by default.
+    pub fn fill_comp(mut self, fill_comp: FillComp) -> Self {
+        self.options.fill_comp = fill_comp;
+        self
+    }
+
+    /// Sets up the rings with `ring_size` entries, binds the socket and registers it in the XSKMAP.
+    ///
+    /// `ring_size` has to be a power of two. Everything set up before a failing step is released again before the
+    /// error is returned.
+    pub fn build(self, ring_size: u32) -> Result<DynRings<'umem, Marker, XM>, Error> {
+        self.build_sized(RingSizes::uniform(ring_size))
+    }
+
+    /// Like [`build`](Self::build), with a separate size for each ring.
+    ///
+    /// The sizes of the fill and completion ring are ignored if the socket falls back to [`DynRings::Two`].
+    pub fn build_sized(self, ring_sizes: RingSizes) -> Result<DynRings<'umem, Marker, XM>, Error> {
+        self.options.build(
+            self.xsk_map.umem.socket(),
+            self.xsk_map.net_device_id,
+            self.queue_id,
+            |socket, bind_lock, bind_flags| {
+                self.xsk_map
+                    .fill_comp_rx_tx_rings(
+                        socket,
+                        bind_lock,
+                        self.queue_id,
+                        self.map_index,
+                        bind_flags,
+                        ring_sizes,
+                    )
+                    .map(DynRings::Four)
+            },
+            |socket, bind_lock, bind_flags| {
+                self.xsk_map
+                    .rx_tx_rings(
+                        socket,
+                        bind_lock,
+                        self.queue_id,
+                        self.map_index,
+                        bind_flags,
+                        ring_sizes,
+                    )
+                    .map(DynRings::Two)
+            },
+        )
+    }
+}
+
+/// The rings of a socket, like [`Rings`](crate::xsk_map::Rings) without the RX-only and TX-only ring sets.
+///
+/// The ring sets can't be split into handles or driven by a [`FillCompDriver`](crate::ring::driver::FillCompDriver).
+pub enum DynRings<'umem, Marker, XM>
+where
+    XM: XskMap,
+{
+    Two(DynRxTxRings<'umem, Marker, XM>),
+    Four(DynFillCompRxTxRings<'umem, Marker, XM>),
+}
+
+pub struct DynRxTxRings<'umem, Marker, XM>
+where
+    XM: XskMap,
+{
+    // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
+    _xsk_map_entry: XskMapEntry<XM>,
+    rx_ring: DynRxRing<'umem, Marker>,
+    tx_ring: DynTxRing<'umem, Marker>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<'umem, Marker, XM> DynRxTxRings<'umem, Marker, XM>
+where
+    XM: XskMap,
+{
+    pub fn rx_ring(&mut self) -> &mut DynRxRing<'umem, Marker> {
+        &mut self.rx_ring
+    }
+
+    pub fn tx_ring(&mut self) -> &mut DynTxRing<'umem, Marker> {
+        &mut self.tx_ring
+    }
+
+    pub fn rings(&mut self) -> (&mut DynRxRing<'umem, Marker>, &mut DynTxRing<'umem, Marker>) {
+        (&mut self.rx_ring, &mut self.tx_ring)
+    }
+}
+
+pub struct DynFillCompRxTxRings<'umem, Marker, XM>
+where
+    XM: XskMap,
+{
+    // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
+    _xsk_map_entry: XskMapEntry<XM>,
+    fill_ring: DynFillRing<'umem, Marker>,
+    completion_ring: DynCompletionRing<'umem, Marker>,
+    rx_ring: DynRxRing<'umem, Marker>,
+    tx_ring: DynTxRing<'umem, Marker>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<'umem, Marker, XM> DynFillCompRxTxRings<'umem, Marker, XM>
+where
+    XM: XskMap,
+{
+    pub fn fill_ring(&mut self) -> &mut DynFillRing<'umem, Marker> {
+        &mut self.fill_ring
+    }
+
+    pub fn completion_ring(&mut self) -> &mut DynCompletionRing<'umem, Marker> {
+        &mut self.completion_ring
+    }
+
+    pub fn rx_ring(&mut self) -> &mut DynRxRing<'umem, Marker> {
+        &mut self.rx_ring
+    }
+
+    pub fn tx_ring(&mut self) -> &mut DynTxRing<'umem, Marker> {
+        &mut self.tx_ring
+    }
+
+    pub fn rings(
+        &mut self,
+    ) -> (
+        &mut DynFillRing<'umem, Marker>,
+        &mut DynCompletionRing<'umem, Marker>,
+        &mut DynRxRing<'umem, Marker>,
+        &mut DynTxRing<'umem, Marker>,
+    ) {
+        (
+            &mut self.fill_ring,
+            &mut self.completion_ring,
+            &mut self.rx_ring,
+            &mut self.tx_ring,
+        )
+    }
+}
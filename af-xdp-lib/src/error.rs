@@ -1,30 +1,244 @@
+use crate::ring::RingKind;
+use crate::socket::BindMode;
+use crate::umem::{DeviceId, QueueId};
 use crate::xsk_map::SetElementError;
 use rustix::io::Errno;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub enum Error {
     Rustix(Errno),
+    /// The marker type is already used by another live UMEM.
     MarkerAlreadyUsed,
-    Wip,
-    XskMapError(String),
+    /// Setting the number of entries of a ring failed, e.g. because the kernel doesn't accept the size.
+    RingSize {
+        ring: RingKind,
+        errno: Errno,
+    },
+    /// Querying the ring offsets or mapping the ring into memory failed.
+    Mmap {
+        ring: RingKind,
+        errno: Errno,
+    },
+    /// Binding the socket to the device and queue failed.
+    Bind {
+        device_id: DeviceId,
+        queue_id: QueueId,
+        errno: Errno,
+    },
+    /// Zero-copy was requested, but the driver of the device doesn't support it.
+    ZeroCopyUnavailable {
+        device_id: DeviceId,
+        queue_id: QueueId,
+        errno: Errno,
+    },
+    /// The bind options of a socket sharing the UMEM differ from the ones of the socket the UMEM is registered with.
+    /// The kernel applies those to all sockets of the UMEM.
+    ///
+    /// The fields are the options of the UMEM socket, `bind_mode` is only [`BindMode::Auto`] if the kernel doesn't
+    /// report the mode it chose.
+    BindOptionsMismatch {
+        device_id: DeviceId,
+        queue_id: QueueId,
+        bind_mode: BindMode,
+        need_wakeup: bool,
+        multi_buffer: bool,
+    },
+    /// The socket doesn't have a fill and completion ring, but is the first socket of the UMEM bound to the device
+    /// and queue. The kernel rejects binding it with EINVAL.
+    FillCompRingsRequired {
+        device_id: DeviceId,
+        queue_id: QueueId,
+    },
+    /// The socket has a fill and completion ring, but another socket of the UMEM is already bound to the device and
+    /// queue. Sockets bound to the same device and queue share its fill and completion ring.
+    ///
+    /// Also returned if the ring set with the fill and completion ring was dropped while other sockets are still bound
+    /// to the device and queue. The kernel keeps the rings until all of them are closed, but they can't be used
+    /// anymore. The socket the UMEM is registered with stays bound until the UMEM is dropped.
+    FillCompRingsInUse {
+        device_id: DeviceId,
+        queue_id: QueueId,
+    },
+    /// The memory region provided for the UMEM isn't page-aligned or too small to hold all chunks.
+    InvalidUmemRegion {
+        addr: usize,
+        len: usize,
+        required_len: usize,
+    },
+    /// The chunk size of a dynamically sized UMEM is smaller than the kernel's minimum, larger than `u32::MAX` or,
+    /// in aligned chunk mode, not a power of two.
+    InvalidChunkSize {
+        chunk_size: usize,
+    },
+    /// The size of a dynamically sized ring is not a power of two or larger than `u32::MAX >> 1`.
+    InvalidRingSize {
+        ring: RingKind,
+        size: u32,
+    },
+    /// Registering the socket in the XSKMAP failed.
+    XskMapRegistration {
+        index: u32,
+        source: SetElementError,
+    },
+    /// Setting the busy polling options of the socket failed, e.g. because of missing `CAP_NET_ADMIN`.
+    BusyPoll {
+        errno: Errno,
+    },
+    /// Waking up the kernel to process a ring failed.
+    Wakeup {
+        ring: RingKind,
+        errno: Errno,
+    },
+    /// Polling a socket or creating, changing or waiting on a [`WaitSet`](crate::ring::wait::WaitSet) failed.
+    Wait {
+        errno: Errno,
+    },
+    /// Registering the socket with the tokio reactor or waiting for its readiness failed.
+    Async(SourceError),
+    /// Loading the XDP program or creating its maps failed.
+    XdpProgramLoad(SourceError),
+    /// Attaching the XDP program failed, e.g. because another program is attached to the device in the same mode or
+    /// the driver doesn't support the mode.
+    XdpProgramAttach {
+        device_id: DeviceId,
+        source: SourceError,
+    },
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Rustix(errno)
+            | Error::RingSize { errno, .. }
+            | Error::Mmap { errno, .. }
+            | Error::Bind { errno, .. }
+            | Error::ZeroCopyUnavailable { errno, .. }
+            | Error::BusyPoll { errno }
+            | Error::Wakeup { errno, .. }
+            | Error::Wait { errno } => Some(errno),
+            Error::MarkerAlreadyUsed
+            | Error::BindOptionsMismatch { .. }
+            | Error::FillCompRingsRequired { .. }
+            | Error::FillCompRingsInUse { .. }
+            | Error::InvalidUmemRegion { .. }
+            | Error::InvalidChunkSize { .. }
+            | Error::InvalidRingSize { .. } => None,
+            Error::XskMapRegistration { source, .. } => Some(source),
+            Error::Async(error) => Some(error.as_ref()),
+            Error::XdpProgramLoad(error) => Some(error.as_ref()),
+            Error::XdpProgramAttach { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Rustix(errno) => {
-                write!(f, "Rustix error, error number: {errno}")
-            }
+            Self::Rustix(errno) => write!(f, "rustix error: {errno}"),
             Self::MarkerAlreadyUsed => {
                 write!(f, "marker already used to create UMEM")
             }
-            Error::Wip => {
-                write!(f, "wip")
+            Error::RingSize { ring, .. } => {
+                write!(f, "failed to set size of {ring:?} ring")
+            }
+            Error::Mmap { ring, .. } => {
+                write!(f, "failed to map {ring:?} ring")
+            }
+            Error::Bind {
+                device_id,
+                queue_id,
+                ..
+            } => {
+                write!(
+                    f,
+                    "failed to bind socket to device {} queue {}",
+                    device_id.0, queue_id.0
+                )
+            }
+            Error::ZeroCopyUnavailable {
+                device_id,
+                queue_id,
+                ..
+            } => {
+                write!(
+                    f,
+                    "zero-copy is not supported on device {} queue {}",
+                    device_id.0, queue_id.0
+                )
+            }
+            Error::BindOptionsMismatch {
+                device_id,
+                queue_id,
+                bind_mode,
+                need_wakeup,
+                multi_buffer,
+            } => {
+                write!(
+                    f,
+                    "bind options of the socket for device {} queue {} differ from the UMEM socket with mode \
+                    {bind_mode:?}, need wakeup {need_wakeup} and multi-buffer {multi_buffer}",
+                    device_id.0, queue_id.0
+                )
+            }
+            Error::FillCompRingsRequired {
+                device_id,
+                queue_id,
+            } => {
+                write!(
+                    f,
+                    "the first socket bound to device {} queue {} requires a fill and completion ring",
+                    device_id.0, queue_id.0
+                )
+            }
+            Error::FillCompRingsInUse {
+                device_id,
+                queue_id,
+            } => {
+                write!(
+                    f,
+                    "device {} queue {} already has a fill and completion ring",
+                    device_id.0, queue_id.0
+                )
+            }
+            Error::InvalidUmemRegion {
+                addr,
+                len,
+                required_len,
+            } => {
+                write!(
+                    f,
+                    "UMEM region at {addr:#x} with {len} bytes isn't page-aligned or smaller than {required_len} bytes"
+                )
+            }
+            Error::InvalidChunkSize { chunk_size } => {
+                write!(f, "chunk size {chunk_size} isn't supported")
+            }
+            Error::InvalidRingSize { ring, size } => {
+                write!(
+                    f,
+                    "size {size} of {ring:?} ring isn't a power of two or too large"
+                )
+            }
+            Error::XskMapRegistration { index, .. } => {
+                write!(f, "failed to register socket at XSKMAP index {index}")
+            }
+            Error::BusyPoll { .. } => f.write_str("failed to set busy polling socket options"),
+            Error::Wakeup { ring, .. } => {
+                write!(f, "failed to wake up the kernel for the {ring:?} ring")
+            }
+            Error::Wait { .. } => f.write_str("failed to wait for socket readiness"),
+            Error::Async(_) => f.write_str("tokio failed to register or wait for the socket"),
+            Error::XdpProgramLoad(_) => f.write_str("failed to load the XDP program"),
+            Error::XdpProgramAttach { device_id, .. } => {
+                write!(
+                    f,
+                    "failed to attach the XDP program to device {}",
+                    device_id.0
+                )
             }
-            Error::XskMapError(message) => f.write_str(message),
         }
     }
 }
@@ -35,8 +249,43 @@ impl From<Errno> for Error {
     }
 }
 
-impl From<SetElementError> for Error {
-    fn from(value: SetElementError) -> Self {
-        Error::XskMapError(value.to_string())
+/// An error of the kernel interface or another library, shared to keep [`Error`] `Clone`.
+///
+/// Two source errors are only equal if they are the same instance.
+#[derive(Debug, Clone)]
+pub struct SourceError(Arc<dyn std::error::Error + Send + Sync + 'static>);
+
+impl<E> From<E> for SourceError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn from(value: E) -> Self {
+        SourceError(Arc::new(value))
+    }
+}
+
+impl AsRef<dyn std::error::Error + Send + Sync + 'static> for SourceError {
+    fn as_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self.0.as_ref()
+    }
+}
+
+impl Display for SourceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl PartialEq for SourceError {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SourceError {}
+
+impl Hash for SourceError {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<()>().hash(state);
     }
 }
@@ -1,5 +1,10 @@
 pub mod descriptor;
+pub mod dynamic;
 pub mod error;
+mod mmap;
+#[cfg(feature = "program")]
+pub mod program;
 pub mod ring;
+pub mod socket;
 pub mod umem;
 pub mod xsk_map;
@@ -0,0 +1,14 @@
+use rustix::io::Errno;
+use rustix::mm::munmap;
+use std::ffi::c_void;
+use std::ptr::NonNull;
+
+/// Turns the address returned by `mmap` for a mapping of `len` bytes into a `NonNull`.
+///
+/// A mapping at the null page can't be used through `NonNull`, it's unmapped again and reported as a bad address.
+pub(crate) fn non_null_mapping(address: *mut c_void, len: usize) -> Result<NonNull<c_void>, Errno> {
+    NonNull::new(address).ok_or_else(|| {
+        unsafe { munmap(address, len) }.unwrap();
+        Errno::FAULT
+    })
+}
@@ -0,0 +1,252 @@
+use crate::dynamic::umem::DynUmem;
+use crate::dynamic::xsk_map::DynXskMapStorage;
+use crate::error::Error;
+use crate::umem::{DeviceId, Umem};
+use crate::xsk_map::XskMapStorage;
+use af_xdp_test_common::{
+    FALLBACK_ABORT, FALLBACK_DROP, FALLBACK_PASS, INDEX_FLOW_HASH, INDEX_QUEUE_ID, RedirectConfig,
+};
+use aya::maps::{Array, MapData};
+use aya::programs::xdp::XdpLinkId;
+use aya::programs::{Xdp, XdpFlags};
+use aya::{Ebpf, EbpfLoader, include_bytes_aligned};
+use std::fmt::{Debug, Formatter};
+use tracing::{error, info};
+
+// Built by build.rs.
+static PROGRAM_OBJECT: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/af-xdp-test"));
+
+const PROGRAM_NAME: &str = "redirect_sock";
+const XSK_MAP_NAME: &str = "SOCKS";
+const CONFIG_MAP_NAME: &str = "CONFIG";
+
+/// Number of entries of the XSKMAP unless set through [`XdpProgramBuilder::map_entries`].
+pub const DEFAULT_MAP_ENTRIES: u32 = 64;
+
+/// How the XDP program is attached to the device.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum AttachMode {
+    /// Generic XDP (`XDP_FLAGS_SKB_MODE`), supported by every driver, but only in copy mode.
+    Generic,
+    /// Native XDP in the driver (`XDP_FLAGS_DRV_MODE`), required for zero-copy.
+    Driver,
+    /// XDP offloaded to the NIC (`XDP_FLAGS_HW_MODE`).
+    Hardware,
+}
+
+/// What the program does with packets without a socket bound to their queue at their XSKMAP index.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum FallbackAction {
+    /// Passes them on to the network stack (`XDP_PASS`).
+    #[default]
+    Pass,
+    /// Drops them (`XDP_DROP`).
+    Drop,
+    /// Drops them and reports them through the `xdp:xdp_exception` tracepoint (`XDP_ABORTED`).
+    Abort,
+}
+
+/// How the program picks the XSKMAP index of the socket a packet is redirected to.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum SocketIndex {
+    /// The queue id the packet was received on.
+    #[default]
+    QueueId,
+    /// `queue_id * sockets_per_queue + flow_hash % sockets_per_queue`, spreading the flows of a queue across several
+    /// sockets bound to it.
+    ///
+    /// The flow hash covers the addresses, protocol and TCP or UDP ports of IPv4 and IPv6 packets, other packets go
+    /// to the first socket of their queue.
+    FlowHash { sockets_per_queue: u32 },
+}
+
+impl AttachMode {
+    fn xdp_flags(self) -> XdpFlags {
+        match self {
+            AttachMode::Generic => XdpFlags::SKB_MODE,
+            AttachMode::Driver => XdpFlags::DRV_MODE,
+            AttachMode::Hardware => XdpFlags::HW_MODE,
+        }
+    }
+}
+
+/// The `redirect_sock` XDP program shipped with this crate, attached to a device and detached again on drop.
+///
+/// The program redirects packets to the socket at the XSKMAP index picked by its [`SocketIndex`] if the socket is
+/// bound to the queue the packet was received on, and handles all other packets according to its [`FallbackAction`].
+pub struct XdpProgram {
+    ebpf: Ebpf,
+    device_id: DeviceId,
+    mode: AttachMode,
+    link_id: Option<XdpLinkId>,
+}
+
+impl Debug for XdpProgram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XdpProgram")
+            .field("device_id", &self.device_id)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+/// Builder for an [`XdpProgram`].
+#[derive(Debug)]
+pub struct XdpProgramBuilder {
+    map_entries: u32,
+    fallback_action: FallbackAction,
+    socket_index: SocketIndex,
+}
+
+impl XdpProgramBuilder {
+    /// Sets the number of entries of the XSKMAP, [`DEFAULT_MAP_ENTRIES`] by default.
+    ///
+    /// Has to be larger than the highest index picked by the [`SocketIndex`].
+    pub fn map_entries(mut self, map_entries: u32) -> Self {
+        self.map_entries = map_entries;
+        self
+    }
+
+    /// Sets what happens to packets without a socket, [`FallbackAction::Pass`] by default.
+    pub fn fallback_action(mut self, fallback_action: FallbackAction) -> Self {
+        self.fallback_action = fallback_action;
+        self
+    }
+
+    /// Sets how the socket of a packet is picked, [`SocketIndex::QueueId`] by default.
+    pub fn socket_index(mut self, socket_index: SocketIndex) -> Self {
+        self.socket_index = socket_index;
+        self
+    }
+
+    fn redirect_config(&self) -> RedirectConfig {
+        let fallback = match self.fallback_action {
+            FallbackAction::Pass => FALLBACK_PASS,
+            FallbackAction::Drop => FALLBACK_DROP,
+            FallbackAction::Abort => FALLBACK_ABORT,
+        };
+        let (index, sockets_per_queue) = match self.socket_index {
+            SocketIndex::QueueId => (INDEX_QUEUE_ID, 1),
+            SocketIndex::FlowHash { sockets_per_queue } => (INDEX_FLOW_HASH, sockets_per_queue),
+        };
+        RedirectConfig {
+            fallback,
+            index,
+            sockets_per_queue,
+        }
+    }
+
+    /// Loads the program and attaches it to the device.
+    pub fn attach(self, device_id: DeviceId, mode: AttachMode) -> Result<XdpProgram, Error> {
+        info!("Loading XDP program.");
+        let mut ebpf = EbpfLoader::new()
+            .map_max_entries(XSK_MAP_NAME, self.map_entries)
+            .load(PROGRAM_OBJECT)
+            .map_err(|error| Error::XdpProgramLoad(error.into()))?;
+
+        let config_map = ebpf
+            .map_mut(CONFIG_MAP_NAME)
+            .expect("the XDP program has a config map");
+        let mut config_map: Array<_, RedirectConfig> =
+            Array::try_from(config_map).expect("the config map is an array");
+        config_map
+            .set(0, self.redirect_config(), 0)
+            .map_err(|error| Error::XdpProgramLoad(error.into()))?;
+
+        let program = xdp_program(&mut ebpf);
+        program
+            .load()
+            .map_err(|error| Error::XdpProgramLoad(error.into()))?;
+
+        info!("Attaching XDP program to device {}.", device_id.0);
+        let link_id = program
+            .attach_to_if_index(device_id.0, mode.xdp_flags())
+            .map_err(|error| Error::XdpProgramAttach {
+                device_id,
+                source: error.into(),
+            })?;
+
+        Ok(XdpProgram {
+            ebpf,
+            device_id,
+            mode,
+            link_id: Some(link_id),
+        })
+    }
+}
+
+impl XdpProgram {
+    /// Loads the program with [`DEFAULT_MAP_ENTRIES`] XSKMAP entries and attaches it to the device.
+    pub fn attach(device_id: DeviceId, mode: AttachMode) -> Result<Self, Error> {
+        Self::builder().attach(device_id, mode)
+    }
+
+    /// Returns a builder to configure the program before attaching it.
+    pub fn builder() -> XdpProgramBuilder {
+        XdpProgramBuilder {
+            map_entries: DEFAULT_MAP_ENTRIES,
+            fallback_action: FallbackAction::default(),
+            socket_index: SocketIndex::default(),
+        }
+    }
+
+    /// The device the program is attached to.
+    pub fn device_id(&self) -> DeviceId {
+        self.device_id
+    }
+
+    /// The mode the program is attached in.
+    pub fn mode(&self) -> AttachMode {
+        self.mode
+    }
+
+    /// Creates the storage for the sockets of `umem`, registered in the XSKMAP of the program.
+    ///
+    /// The storage borrows the program, so its sockets are removed from the XSKMAP before the program is detached.
+    pub fn xsk_map_storage<'umem, Marker, const CHUNK_SIZE: usize>(
+        &mut self,
+        umem: &'umem Umem<Marker, CHUNK_SIZE>,
+    ) -> XskMapStorage<'umem, aya::maps::XskMap<&mut MapData>, Marker, CHUNK_SIZE> {
+        let device_id = self.device_id;
+        XskMapStorage::new(self.xsk_map(), device_id, umem)
+    }
+
+    /// Creates the storage for the sockets of `umem`, see [`xsk_map_storage`](XdpProgram::xsk_map_storage).
+    pub fn dyn_xsk_map_storage<'umem, Marker>(
+        &mut self,
+        umem: &'umem DynUmem<Marker>,
+    ) -> DynXskMapStorage<'umem, aya::maps::XskMap<&mut MapData>, Marker> {
+        let device_id = self.device_id;
+        DynXskMapStorage::new(self.xsk_map(), device_id, umem)
+    }
+
+    fn xsk_map(&mut self) -> aya::maps::XskMap<&mut MapData> {
+        let map = self
+            .ebpf
+            .map_mut(XSK_MAP_NAME)
+            .expect("the XDP program has an XSKMAP");
+        aya::maps::XskMap::try_from(map).expect("the XDP program has an XSKMAP")
+    }
+}
+
+impl Drop for XdpProgram {
+    fn drop(&mut self) {
+        let Some(link_id) = self.link_id.take() else {
+            return;
+        };
+        info!("Detaching XDP program from device {}.", self.device_id.0);
+        if let Err(error) = xdp_program(&mut self.ebpf).detach(link_id) {
+            error!(
+                "failed to detach XDP program from device {}: {}",
+                self.device_id.0, error
+            );
+        }
+    }
+}
+
+fn xdp_program(ebpf: &mut Ebpf) -> &mut Xdp {
+    ebpf.program_mut(PROGRAM_NAME)
+        .expect("the object contains the XDP program")
+        .try_into()
+        .expect("the XDP program is of type XDP")
+}
@@ -0,0 +1,220 @@
+use crate::descriptor::{FillCompFrameDescriptor, RxTxFrameDescriptor};
+use crate::error::Error;
+use crate::ring::{CompletionRing, RingKind, RxRing, TxRing, wake_rx, wake_tx};
+use std::os::fd::{BorrowedFd, OwnedFd};
+use tokio::io::Interest;
+use tokio::io::unix::AsyncFd;
+
+/// An [`RxRing`] whose readiness is driven by the tokio reactor instead of busy polling.
+///
+/// The socket becomes readable once the kernel produced entries on the RX ring.
+pub struct AsyncRxRing<'ring, 'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize> {
+    ring: &'ring mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    socket: AsyncFd<OwnedFd>,
+}
+
+impl<'ring, 'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
+    AsyncRxRing<'ring, 'umem, Marker, CHUNK_SIZE, RING_SIZE>
+{
+    /// Registers the socket of `ring` with the reactor of the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime with IO enabled.
+    pub fn new(
+        ring: &'ring mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    ) -> Result<Self, Error> {
+        let socket = register(ring.socket(), Interest::READABLE)?;
+        Ok(Self { ring, socket })
+    }
+
+    pub fn ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+        self.ring
+    }
+
+    /// Waits until the RX ring has at least one filled entry.
+    pub async fn readable(&mut self) -> Result<(), Error> {
+        loop {
+            if !self.ring.is_empty() {
+                return Ok(());
+            }
+            // Zero-copy drivers report the need for a wakeup on the fill ring, which isn't available here. An
+            // unneeded wakeup is cheap compared to a missed one and only happens before going to sleep.
+            wake_rx(self.ring.socket(), RingKind::Rx)?;
+
+            let mut guard = self
+                .socket
+                .readable()
+                .await
+                .map_err(|error| Error::Async(error.into()))?;
+            // The guard is taken before checking the ring, `clear_ready` keeps the readiness if the kernel signaled
+            // new entries in between.
+            if !self.ring.is_empty() {
+                return Ok(());
+            }
+            guard.clear_ready();
+        }
+    }
+
+    /// Waits for filled entries and pops up to `count` descriptors into `descriptors`.
+    ///
+    /// Returns the number of popped descriptors, which is only zero if `count` is zero.
+    pub async fn recv_batch(
+        &mut self,
+        count: u32,
+        descriptors: &mut Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> Result<u32, Error> {
+        if count == 0 {
+            return Ok(0);
+        }
+        self.readable().await?;
+        Ok(self.ring.pop_batch(count, descriptors))
+    }
+}
+
+/// A [`TxRing`] whose readiness is driven by the tokio reactor instead of busy polling.
+///
+/// The socket becomes writable once the kernel consumed entries and at most half of the TX ring is filled.
+/// Completed frames are awaited with an [`AsyncCompletionRing`].
+pub struct AsyncTxRing<'ring, 'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize> {
+    ring: &'ring mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    socket: AsyncFd<OwnedFd>,
+}
+
+impl<'ring, 'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
+    AsyncTxRing<'ring, 'umem, Marker, CHUNK_SIZE, RING_SIZE>
+{
+    /// Registers the socket of `ring` with the reactor of the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime with IO enabled.
+    pub fn new(
+        ring: &'ring mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    ) -> Result<Self, Error> {
+        let socket = register(ring.socket(), Interest::WRITABLE)?;
+        Ok(Self { ring, socket })
+    }
+
+    pub fn ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+        self.ring
+    }
+
+    /// Waits until the TX ring has at least one free entry.
+    pub async fn writable(&mut self) -> Result<(), Error> {
+        loop {
+            if !self.ring.is_full() {
+                return Ok(());
+            }
+            // In copy mode, the kernel only consumes TX entries while handling a wakeup.
+            wake_tx(self.ring.socket())?;
+
+            let mut guard = self
+                .socket
+                .writable()
+                .await
+                .map_err(|error| Error::Async(error.into()))?;
+            if !self.ring.is_full() {
+                return Ok(());
+            }
+            guard.clear_ready();
+        }
+    }
+
+    /// Waits for free entries, pushes descriptors from the end of `descriptors` and wakes up the kernel if needed.
+    ///
+    /// Returns the number of pushed descriptors, descriptors that didn't fit into the ring are left in
+    /// `descriptors`.
+    pub async fn send_batch(
+        &mut self,
+        descriptors: &mut Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> Result<u32, Error> {
+        if descriptors.is_empty() {
+            return Ok(0);
+        }
+        self.writable().await?;
+        let pushed = self.ring.push_batch(descriptors);
+        self.ring.poke()?;
+        Ok(pushed)
+    }
+}
+
+/// A [`CompletionRing`] whose readiness is driven by the tokio reactor instead of busy polling.
+///
+/// The kernel signals completions as writability of the socket the ring was created with, which needs a TX ring. In
+/// copy mode, the kernel only completes frames while handling a TX wakeup, which
+/// [`readable`](AsyncCompletionRing::readable) triggers before waiting.
+pub struct AsyncCompletionRing<
+    'ring,
+    'umem,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const RING_SIZE: usize,
+> {
+    ring: &'ring mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    socket: AsyncFd<OwnedFd>,
+}
+
+impl<'ring, 'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
+    AsyncCompletionRing<'ring, 'umem, Marker, CHUNK_SIZE, RING_SIZE>
+{
+    /// Registers the socket of `ring` with the reactor of the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime with IO enabled.
+    pub fn new(
+        ring: &'ring mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    ) -> Result<Self, Error> {
+        let socket = register(ring.socket(), Interest::WRITABLE)?;
+        Ok(Self { ring, socket })
+    }
+
+    pub fn ring(&mut self) -> &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+        self.ring
+    }
+
+    /// Waits until the completion ring has at least one filled entry.
+    pub async fn readable(&mut self) -> Result<(), Error> {
+        loop {
+            if !self.ring.is_empty() {
+                return Ok(());
+            }
+            wake_tx(self.ring.socket())?;
+
+            let mut guard = self
+                .socket
+                .writable()
+                .await
+                .map_err(|error| Error::Async(error.into()))?;
+            if !self.ring.is_empty() {
+                return Ok(());
+            }
+            guard.clear_ready();
+        }
+    }
+
+    /// Waits for completed frames and pops up to `count` descriptors into `descriptors`.
+    ///
+    /// Returns the number of popped descriptors, which is only zero if `count` is zero.
+    pub async fn recv_batch(
+        &mut self,
+        count: u32,
+        descriptors: &mut Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> Result<u32, Error> {
+        if count == 0 {
+            return Ok(0);
+        }
+        self.readable().await?;
+        Ok(self.ring.pop_batch(count, descriptors))
+    }
+}
+
+/// Registers a duplicate of the socket, epoll rejects registering the same file descriptor twice, which would rule out
+/// an RX and TX wrapper for the same socket.
+fn register(socket: BorrowedFd, interest: Interest) -> Result<AsyncFd<OwnedFd>, Error> {
+    let socket = socket
+        .try_clone_to_owned()
+        .map_err(|error| Error::Async(error.into()))?;
+    AsyncFd::with_interest(socket, interest).map_err(|error| Error::Async(error.into()))
+}
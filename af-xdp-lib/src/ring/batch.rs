@@ -0,0 +1,209 @@
+use crate::descriptor::Descriptor;
+use crate::ring::{Consumer, Producer, Ring, RingSize};
+use std::fmt::Debug;
+use tracing::trace;
+
+/// Slots reserved on a producer ring, published with a single producer update.
+///
+/// Equivalent to `xsk_ring_prod__reserve`/`xsk_ring_prod__submit` in libxdp:
+/// https://github.com/xdp-project/xdp-tools/blob/master/headers/xdp/xsk.h
+///
+/// Descriptors written into the batch are handed to the kernel when the batch is submitted or dropped.
+/// Reserved slots that were not written to are given back to the ring.
+pub struct ProducerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    ring: &'ring mut Ring<'umem, Producer, FrameDescriptor, Marker, Size>,
+    start: u32,
+    reserved: u32,
+    written: u32,
+}
+
+impl<'ring, 'umem, FrameDescriptor, Marker, Size>
+    ProducerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    pub(crate) fn new(
+        ring: &'ring mut Ring<'umem, Producer, FrameDescriptor, Marker, Size>,
+        count: u32,
+    ) -> Self {
+        let reserved = count.min(ring.free_entries_for(count));
+        let start = ring.cached_producer();
+        trace!("Reserved {reserved} of {count} requested entries.");
+
+        Self {
+            ring,
+            start,
+            reserved,
+            written: 0,
+        }
+    }
+
+    /// Number of slots reserved on the ring.
+    pub fn reserved(&self) -> u32 {
+        self.reserved
+    }
+
+    /// Number of descriptors written into the batch so far.
+    pub fn written(&self) -> u32 {
+        self.written
+    }
+
+    /// Number of reserved slots that have not been written to yet.
+    pub fn remaining(&self) -> u32 {
+        self.reserved - self.written
+    }
+
+    /// Writes the descriptor into the next reserved slot.
+    ///
+    /// Returns the descriptor if all reserved slots have been written to.
+    pub fn write(&mut self, input: FrameDescriptor) -> Result<(), FrameDescriptor> {
+        if self.remaining() == 0 {
+            return Err(input);
+        }
+        self.ring.umem_memory.check_chunk(input.chunk());
+
+        unsafe {
+            self.ring.write_descriptor(
+                self.start.wrapping_add(self.written),
+                input.into_ring_repr(),
+            )
+        };
+        self.written += 1;
+
+        Ok(())
+    }
+
+    /// Publishes all written descriptors with a single producer update.
+    pub fn submit(self) {
+        // Publishing happens on drop.
+    }
+}
+
+impl<'ring, 'umem, FrameDescriptor, Marker, Size> Drop
+    for ProducerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    fn drop(&mut self) {
+        trace!("Submitting {} entries.", self.written);
+        if self.written > 0 {
+            self.ring
+                .set_producer(self.start.wrapping_add(self.written));
+        }
+    }
+}
+
+/// Entries peeked from a consumer ring, released with a single consumer update.
+///
+/// Equivalent to `xsk_ring_cons__peek`/`xsk_ring_cons__release` in libxdp:
+/// https://github.com/xdp-project/xdp-tools/blob/master/headers/xdp/xsk.h
+///
+/// The batch is an iterator over the peeked descriptors. Descriptors taken from the iterator are released
+/// to the kernel when the batch is released or dropped, entries that weren't taken stay in the ring.
+pub struct ConsumerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    ring: &'ring mut Ring<'umem, Consumer, FrameDescriptor, Marker, Size>,
+    start: u32,
+    available: u32,
+    read: u32,
+}
+
+impl<'ring, 'umem, FrameDescriptor, Marker, Size>
+    ConsumerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    pub(crate) fn new(
+        ring: &'ring mut Ring<'umem, Consumer, FrameDescriptor, Marker, Size>,
+        count: u32,
+    ) -> Self {
+        let available = count.min(ring.filled_entries_for(count));
+        let start = ring.cached_consumer();
+        trace!("Peeked {available} of {count} requested entries.");
+
+        Self {
+            ring,
+            start,
+            available,
+            read: 0,
+        }
+    }
+
+    /// Number of entries peeked from the ring.
+    pub fn available(&self) -> u32 {
+        self.available
+    }
+
+    /// Number of descriptors taken from the batch so far.
+    pub fn read(&self) -> u32 {
+        self.read
+    }
+
+    /// Releases all descriptors taken from the batch with a single consumer update.
+    pub fn release(self) {
+        // Releasing happens on drop.
+    }
+}
+
+impl<'ring, 'umem, FrameDescriptor, Marker, Size> Iterator
+    for ConsumerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    type Item = FrameDescriptor;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.read == self.available {
+            return None;
+        }
+
+        let desc = FrameDescriptor::from_ring_repr(
+            unsafe {
+                self.ring
+                    .read_descriptor(self.start.wrapping_add(self.read))
+            },
+            self.ring.umem_memory,
+        );
+        self.read += 1;
+
+        Some(desc)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.available - self.read) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'ring, 'umem, FrameDescriptor, Marker, Size> ExactSizeIterator
+    for ConsumerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+}
+
+impl<'ring, 'umem, FrameDescriptor, Marker, Size> Drop
+    for ConsumerBatch<'ring, 'umem, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
+{
+    fn drop(&mut self) {
+        trace!("Releasing {} entries.", self.read);
+        if self.read > 0 {
+            self.ring.set_consumer(self.start.wrapping_add(self.read));
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::descriptor::FillCompFrameDescriptor;
+use crate::error::Error;
+use crate::ring::{CompletionRing, FillRing};
+use crate::umem::frame_pool::FrameCache;
+use std::any::type_name;
+use std::fmt::{Debug, Formatter};
+
+/// Where a [`FillCompDriver`] takes free frames for the fill ring from and returns completed frames to.
+pub trait FrameSource<'umem, Marker, const CHUNK_SIZE: usize> {
+    /// Pushes up to `count` free frames to the fill ring, returns the number of pushed frames.
+    fn fill<const RING_SIZE: usize>(
+        &mut self,
+        fill_ring: &mut FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: usize,
+    ) -> u32;
+
+    /// Pops up to `count` completed frames from the completion ring, returns the number of popped frames.
+    fn complete<const RING_SIZE: usize>(
+        &mut self,
+        completion_ring: &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: u32,
+    ) -> u32;
+}
+
+/// A plain free list, frames are taken from and returned to its end.
+impl<'umem, Marker, const CHUNK_SIZE: usize> FrameSource<'umem, Marker, CHUNK_SIZE>
+    for Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>
+{
+    fn fill<const RING_SIZE: usize>(
+        &mut self,
+        fill_ring: &mut FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: usize,
+    ) -> u32 {
+        let mut descriptors = self.split_off(self.len().saturating_sub(count));
+        let pushed = fill_ring.push_batch(&mut descriptors);
+        self.append(&mut descriptors);
+        pushed
+    }
+
+    fn complete<const RING_SIZE: usize>(
+        &mut self,
+        completion_ring: &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: u32,
+    ) -> u32 {
+        completion_ring.pop_batch(count, self)
+    }
+}
+
+/// Counts the frames on the rings of its pool.
+impl<'pool, 'umem, Marker, const CHUNK_SIZE: usize> FrameSource<'umem, Marker, CHUNK_SIZE>
+    for FrameCache<'pool, 'umem, Marker, CHUNK_SIZE>
+{
+    fn fill<const RING_SIZE: usize>(
+        &mut self,
+        fill_ring: &mut FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: usize,
+    ) -> u32 {
+        FrameCache::fill(self, fill_ring, count)
+    }
+
+    fn complete<const RING_SIZE: usize>(
+        &mut self,
+        completion_ring: &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: u32,
+    ) -> u32 {
+        FrameCache::complete(self, completion_ring, count)
+    }
+}
+
+/// Starvation and throughput counters of a [`FillCompDriver`].
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct DriverStats {
+    /// Frames returned from the completion ring to the source.
+    pub completed: u64,
+    /// Frames pushed to the fill ring.
+    pub filled: u64,
+    /// Times the kernel consumed all entries the driver left on the fill ring, received packets might have been
+    /// dropped. Counted once per starvation, not on every call while the fill ring stays empty.
+    pub fill_ring_empty: u64,
+    /// Times the source didn't have enough frames to refill the fill ring to the watermark.
+    pub source_empty: u64,
+}
+
+/// Recycles completed frames and keeps the fill ring topped up, see
+/// [`FillCompRxTxRings::into_driven`](crate::xsk_map::FillCompRxTxRings::into_driven).
+pub struct FillCompDriver<
+    'umem,
+    Marker,
+    Source,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+> {
+    fill_ring: FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+    completion_ring: CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+    source: Source,
+    watermark: u32,
+    stats: DriverStats,
+    // Whether the fill ring had entries after the last call of `drive`.
+    fill_ring_filled: bool,
+}
+
+impl<
+    'umem,
+    Marker,
+    Source,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+> Debug for FillCompDriver<'umem, Marker, Source, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("FillCompDriver<{}>", type_name::<Marker>()))
+            .field("watermark", &self.watermark)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl<
+    'umem,
+    Marker,
+    Source,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+> FillCompDriver<'umem, Marker, Source, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE>
+where
+    Source: FrameSource<'umem, Marker, CHUNK_SIZE>,
+{
+    pub(crate) fn new(
+        fill_ring: FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+        completion_ring: CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+        source: Source,
+        watermark: u32,
+    ) -> Self {
+        assert!(
+            watermark <= FILL_SIZE as u32,
+            "watermark {watermark} exceeds the fill ring size {FILL_SIZE}"
+        );
+        Self {
+            fill_ring,
+            completion_ring,
+            source,
+            watermark,
+            stats: DriverStats::default(),
+            fill_ring_filled: false,
+        }
+    }
+
+    /// Returns completed frames to the source, refills the fill ring to the watermark and wakes up the kernel if it
+    /// needs a wakeup.
+    ///
+    /// Has to be called regularly, e.g. once per iteration of the processing loop.
+    pub fn drive(&mut self) -> Result<(), Error> {
+        let completed = self
+            .source
+            .complete(&mut self.completion_ring, COMPLETION_SIZE as u32);
+        self.stats.completed += u64::from(completed);
+
+        // Entries the kernel didn't consume yet.
+        let filled = FILL_SIZE as u32 - self.fill_ring.free_entries_for(FILL_SIZE as u32);
+        if filled == 0 && self.fill_ring_filled {
+            self.stats.fill_ring_empty += 1;
+        }
+        let mut pushed = 0;
+        if filled < self.watermark {
+            let missing = self.watermark - filled;
+            pushed = self.source.fill(&mut self.fill_ring, missing as usize);
+            self.stats.filled += u64::from(pushed);
+            if pushed < missing {
+                self.stats.source_empty += 1;
+            }
+        }
+        self.fill_ring_filled = filled + pushed > 0;
+
+        self.fill_ring.poke()
+    }
+
+    pub fn stats(&self) -> DriverStats {
+        self.stats
+    }
+
+    pub fn watermark(&self) -> u32 {
+        self.watermark
+    }
+
+    /// Sets the number of entries the fill ring is refilled to.
+    ///
+    /// # Panics
+    ///
+    /// If `watermark` exceeds the size of the fill ring.
+    pub fn set_watermark(&mut self, watermark: u32) {
+        assert!(
+            watermark <= FILL_SIZE as u32,
+            "watermark {watermark} exceeds the fill ring size {FILL_SIZE}"
+        );
+        self.watermark = watermark;
+    }
+
+    /// The frame source, e.g. to return received frames that aren't transmitted.
+    pub fn source(&mut self) -> &mut Source {
+        &mut self.source
+    }
+
+    pub fn fill_ring(&mut self) -> &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE> {
+        &mut self.fill_ring
+    }
+
+    pub fn completion_ring(
+        &mut self,
+    ) -> &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE> {
+        &mut self.completion_ring
+    }
+}
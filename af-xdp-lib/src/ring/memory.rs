@@ -1,5 +1,6 @@
-use crate::descriptor::Descriptor;
 use crate::error::Error;
+use crate::mmap::non_null_mapping;
+use crate::ring::RingKind;
 use rustix::mm::{MapFlags, ProtFlags, mmap, munmap};
 use rustix::net::xdp::{XdpRingFlags, XdpRingOffset};
 use std::ffi::c_void;
@@ -8,35 +9,29 @@ use std::ptr::NonNull;
 use std::sync::atomic::AtomicU32;
 use std::sync::atomic::Ordering::{Acquire, Release};
 
-pub(crate) struct RingMemory<
-    'umem,
-    Marker,
-    FrameDescriptor,
-    const CHUNK_SIZE: usize,
-    const RING_SIZE: usize,
-> where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE>,
-{
+/// The shared memory of a ring holding `size` descriptors of type `Desc`.
+///
+/// Descriptor indices aren't wrapped here, the const and the dynamically sized rings wrap them with their size.
+pub(crate) struct RingMemory<Desc> {
     mmap_address: NonNull<c_void>,
     mmap_size: usize,
-    descriptor_memory: NonNull<FrameDescriptor::InRingDescriptorType>,
+    descriptor_memory: NonNull<Desc>,
     producer: NonNull<AtomicU32>,
     consumer: NonNull<AtomicU32>,
     flags: Option<NonNull<u32>>,
 }
 
-impl<'umem, Marker, FrameDescriptor, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-    RingMemory<'umem, Marker, FrameDescriptor, CHUNK_SIZE, RING_SIZE>
+impl<Desc> RingMemory<Desc>
 where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE>,
+    Desc: Copy,
 {
     pub(crate) fn new(
         socket: BorrowedFd,
-        mmap_offset: u64,
+        ring: RingKind,
         ring_offsets: XdpRingOffset,
+        size: usize,
     ) -> Result<Self, Error> {
-        let mmap_size = (ring_offsets.desc as usize)
-            + RING_SIZE * size_of::<FrameDescriptor::InRingDescriptorType>();
+        let mmap_size = (ring_offsets.desc as usize) + size * size_of::<Desc>();
 
         let mmap_address = unsafe {
             mmap(
@@ -45,15 +40,13 @@ where
                 ProtFlags::READ | ProtFlags::WRITE,
                 MapFlags::SHARED | MapFlags::POPULATE,
                 socket,
-                mmap_offset,
+                ring.mmap_offset(),
             )
-        }?;
+        }
+        .map_err(|errno| Error::Mmap { ring, errno })?;
 
-        let mmap_address = NonNull::new(mmap_address)
-            .ok_or(Error::Wip)
-            .inspect_err(|_| {
-                unsafe { munmap(mmap_address, mmap_size) }.unwrap();
-            })?;
+        let mmap_address = non_null_mapping(mmap_address, mmap_size)
+            .map_err(|errno| Error::Mmap { ring, errno })?;
 
         Ok(Self {
             mmap_address,
@@ -96,18 +89,11 @@ where
     fn descriptors_memory_ptr(
         mmap_address: NonNull<c_void>,
         offsets: &XdpRingOffset,
-    ) -> NonNull<FrameDescriptor::InRingDescriptorType> {
+    ) -> NonNull<Desc> {
         let descriptor_memory = unsafe { mmap_address.byte_add(offsets.desc as usize) };
         descriptor_memory.cast()
     }
 
-    /// Returns a value with all bits of the index value set.
-    ///
-    /// The ring index overflows and the ring index bits are used to cut off the overflow part.
-    fn ring_index_bits() -> usize {
-        RING_SIZE - 1
-    }
-
     fn producer_ref(&self) -> &AtomicU32 {
         unsafe { self.producer.as_ref() }
     }
@@ -139,31 +125,20 @@ where
         Some(XdpRingFlags::from_bits_retain(flags))
     }
 
-    pub(crate) unsafe fn read_descriptor(
-        &self,
-        mut offset: usize,
-    ) -> FrameDescriptor::InRingDescriptorType {
-        offset &= Self::ring_index_bits();
-        let desc_ptr = unsafe { self.descriptor_memory.add(offset) };
+    /// `index` has to be smaller than the size of the ring.
+    pub(crate) unsafe fn read_descriptor(&self, index: usize) -> Desc {
+        let desc_ptr = unsafe { self.descriptor_memory.add(index) };
         unsafe { desc_ptr.read() }
     }
 
-    pub(crate) unsafe fn write_descriptor(
-        &self,
-        mut offset: usize,
-        desc: FrameDescriptor::InRingDescriptorType,
-    ) {
-        offset &= Self::ring_index_bits();
-        let desc_ptr = unsafe { self.descriptor_memory.add(offset) };
+    /// `index` has to be smaller than the size of the ring.
+    pub(crate) unsafe fn write_descriptor(&self, index: usize, desc: Desc) {
+        let desc_ptr = unsafe { self.descriptor_memory.add(index) };
         unsafe { desc_ptr.write(desc) }
     }
 }
 
-impl<'umem, Marker, FrameDescriptor, const CHUNK_SIZE: usize, const RING_SIZE: usize> Drop
-    for RingMemory<'umem, Marker, FrameDescriptor, CHUNK_SIZE, RING_SIZE>
-where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE>,
-{
+impl<Desc> Drop for RingMemory<Desc> {
     fn drop(&mut self) {
         unsafe { munmap(self.mmap_address.as_ptr(), self.mmap_size) }.unwrap()
     }
@@ -1,70 +1,82 @@
-mod memory;
-
-use crate::descriptor::{Descriptor, FillCompFrameDescriptor, RxTxFrameDescriptor};
+#[cfg(feature = "tokio")]
+pub mod async_ring;
+pub mod batch;
+pub mod driver;
+pub(crate) mod memory;
+pub mod wait;
+
+use crate::descriptor::packet::Packet;
+use crate::descriptor::{
+    Chunk, Descriptor, FillCompDescriptor, FillCompFrameDescriptor, RxTxDescriptor,
+    RxTxFrameDescriptor,
+};
 use crate::error::Error;
+use crate::ring::batch::{ConsumerBatch, ProducerBatch};
 use crate::ring::memory::RingMemory;
+use crate::ring::sealed::SealedRingSize;
+use crate::ring::wait::poll_socket;
+use crate::umem::Umem;
 use crate::umem::memory::UmemMemory;
+use crate::umem::registration::UmemSocket;
+use rustix::event::PollFlags;
+use rustix::io::Errno;
 use rustix::net::sockopt::{
     set_xdp_rx_ring_size, set_xdp_tx_ring_size, set_xdp_umem_completion_ring_size,
     set_xdp_umem_fill_ring_size, xdp_mmap_offsets, xdp_options, xdp_statistics,
 };
 use rustix::net::xdp::{
     SocketAddrXdp, SocketAddrXdpFlags, XDP_PGOFF_RX_RING, XDP_PGOFF_TX_RING,
-    XDP_UMEM_PGOFF_COMPLETION_RING, XDP_UMEM_PGOFF_FILL_RING, XdpOptionsFlags, XdpRingFlags,
-    XdpRingOffset, XdpStatistics,
+    XDP_UMEM_PGOFF_COMPLETION_RING, XDP_UMEM_PGOFF_FILL_RING, XdpDescOptions, XdpOptionsFlags,
+    XdpRingFlags, XdpRingOffset, XdpStatistics,
 };
 use rustix::net::{RecvFlags, SendFlags, recvfrom, sendto};
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use std::os::fd::{AsFd, OwnedFd};
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
 use std::sync::Arc;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::Duration;
 use tracing::{info, trace, warn};
 
 /// https://github.com/xdp-project/xdp-tools/blob/master/headers/xdp/xsk.h#L32
 /// https://github.com/torvalds/linux/blob/master/net/xdp/xsk_queue.h
-pub struct Ring<
-    'umem,
-    RingType,
-    FrameDescriptor,
-    Marker,
-    const CHUNK_SIZE: usize,
-    const RING_SIZE: usize,
-> where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE> + Debug,
+pub struct Ring<'umem, RingType, FrameDescriptor, Marker, Size>
+where
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
 {
-    ring_memory: RingMemory<'umem, Marker, FrameDescriptor, CHUNK_SIZE, RING_SIZE>,
+    ring_memory: RingMemory<FrameDescriptor::InRingDescriptorType>,
+    size: Size,
+    // Local copies of the producer and consumer index, like `cached_prod`/`cached_cons` in `xsk_queue.h`.
+    // The index owned by this side of the ring is always up to date, the index owned by the kernel is refreshed
+    // from the shared memory when the cached values indicate a full or empty ring, and for every count that
+    // shrinks as the kernel advances, like the filled entries of a producer ring.
+    // Atomics instead of `Cell` to keep the ring `Sync`, they are only ever accessed with relaxed ordering.
+    cached_producer: AtomicU32,
+    cached_consumer: AtomicU32,
     umem_memory: &'umem UmemMemory,
     socket: Arc<OwnedFd>,
     ring_type: PhantomData<RingType>,
-    marker: PhantomData<Marker>,
+    marker: PhantomData<fn(Marker)>,
 }
 
 // Safety:
 // Ring is not Send because NonNull has no guarantees to make it Send.
 // The pointers are never altered, and the pointed to memory/values are safe to exclusively access from other threads.
-unsafe impl<
-    'umem,
-    RingType,
-    FrameDescriptor,
-    Marker,
-    const CHUNK_SIZE: usize,
-    const RING_SIZE: usize,
-> Send for Ring<'umem, RingType, FrameDescriptor, Marker, CHUNK_SIZE, RING_SIZE>
+unsafe impl<'umem, RingType, FrameDescriptor, Marker, Size> Send
+    for Ring<'umem, RingType, FrameDescriptor, Marker, Size>
 where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE> + Debug,
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
 {
 }
 
-unsafe impl<
-    'umem,
-    RingType,
-    FrameDescriptor,
-    Marker,
-    const CHUNK_SIZE: usize,
-    const RING_SIZE: usize,
-> Sync for Ring<'umem, RingType, FrameDescriptor, Marker, CHUNK_SIZE, RING_SIZE>
+unsafe impl<'umem, RingType, FrameDescriptor, Marker, Size> Sync
+    for Ring<'umem, RingType, FrameDescriptor, Marker, Size>
 where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE> + Debug,
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
 {
 }
 
@@ -73,48 +85,247 @@ pub struct Consumer;
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
 pub struct Producer;
 
+pub(crate) mod sealed {
+    use crate::error::Error;
+    use crate::ring::RingKind;
+
+    pub trait SealedRingSize {
+        /// Fails if the size isn't a power of two the ring indices can overflow with.
+        fn check(&self, ring: RingKind) -> Result<(), Error>;
+
+        fn get(&self) -> u32;
+    }
+}
+
+/// Where a [`Ring`] takes its number of entries from, [`ConstSize`] or [`DynSize`].
+pub trait RingSize: SealedRingSize + Copy + Debug {}
+
+/// A ring size known at compile time, checked when the ring is built.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct ConstSize<const RING_SIZE: usize>;
+
+impl<const RING_SIZE: usize> RingSize for ConstSize<RING_SIZE> {}
+
+impl<const RING_SIZE: usize> SealedRingSize for ConstSize<RING_SIZE> {
+    fn check(&self, _ring: RingKind) -> Result<(), Error> {
+        const {
+            assert!(
+                RING_SIZE as u32 <= u32::MAX >> 1,
+                "RING_SIZE may not be larger than u32::MAX >> 1 to allow overflow"
+            );
+            assert!(
+                RING_SIZE.is_power_of_two(),
+                "RING_SIZE must be a power of two"
+            );
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> u32 {
+        RING_SIZE as u32
+    }
+}
+
+/// A ring size only known at runtime, see [`dynamic`](crate::dynamic).
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct DynSize(pub(crate) u32);
+
+impl RingSize for DynSize {}
+
+impl SealedRingSize for DynSize {
+    fn check(&self, ring: RingKind) -> Result<(), Error> {
+        if !self.0.is_power_of_two() || self.0 > u32::MAX >> 1 {
+            return Err(Error::InvalidRingSize { ring, size: self.0 });
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+/// The four ring types of an XDP socket.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum RingKind {
+    Fill,
+    Completion,
+    Rx,
+    Tx,
+}
+
+impl RingKind {
+    fn mmap_offset(self) -> u64 {
+        match self {
+            RingKind::Fill => XDP_UMEM_PGOFF_FILL_RING,
+            RingKind::Completion => XDP_UMEM_PGOFF_COMPLETION_RING,
+            RingKind::Rx => XDP_PGOFF_RX_RING,
+            RingKind::Tx => XDP_PGOFF_TX_RING,
+        }
+    }
+}
+
+/// Sets the number of entries of a ring.
+///
+/// The kernel allows this only once per ring and socket.
+pub(crate) fn set_ring_size(socket: BorrowedFd, ring: RingKind, size: u32) -> Result<(), Error> {
+    match ring {
+        RingKind::Fill => set_xdp_umem_fill_ring_size(socket, size),
+        RingKind::Completion => set_xdp_umem_completion_ring_size(socket, size),
+        RingKind::Rx => set_xdp_rx_ring_size(socket, size),
+        RingKind::Tx => set_xdp_tx_ring_size(socket, size),
+    }
+    .map_err(|errno| Error::RingSize { ring, errno })
+}
+
+/// Queries the offsets of a ring within its mapping.
+pub(crate) fn ring_offsets(socket: BorrowedFd, ring: RingKind) -> Result<XdpRingOffset, Error> {
+    let offsets = xdp_mmap_offsets(socket).map_err(|errno| Error::Mmap { ring, errno })?;
+    let offsets = match ring {
+        RingKind::Fill => offsets.fr,
+        RingKind::Completion => offsets.cr,
+        RingKind::Rx => offsets.rx,
+        RingKind::Tx => offsets.tx,
+    };
+    info!("Offsets: {offsets:?}");
+    Ok(offsets)
+}
+
+/// Wakes up the kernel to process the RX side of the socket, the fill and RX ring.
+pub(crate) fn wake_rx(socket: BorrowedFd, ring: RingKind) -> Result<(), Error> {
+    match recvfrom::<_, &mut [u8; 0]>(socket, &mut [], RecvFlags::DONTWAIT) {
+        Ok(_) => Ok(()),
+        Err(errno) if is_transient_wakeup_error(errno) => Ok(()),
+        Err(errno) => Err(Error::Wakeup { ring, errno }),
+    }
+}
+
+/// Wakes up the kernel to process the TX ring.
+pub(crate) fn wake_tx(socket: BorrowedFd) -> Result<(), Error> {
+    let sockaddr_xdp = SocketAddrXdp::new(
+        // Not used in sendmsg for XDP.
+        // https://github.com/torvalds/linux/blob/v6.10/net/xdp/xsk.c#L905-L948
+        SocketAddrXdpFlags::empty(),
+        // Not used in sendmsg for XDP.
+        // https://github.com/torvalds/linux/blob/v6.10/net/xdp/xsk.c#L905-L948
+        0,
+        // Not used in sendmsg for XDP.
+        // https://github.com/torvalds/linux/blob/v6.10/net/xdp/xsk.c#L905-L948
+        0,
+    );
+    match sendto(socket, &[], SendFlags::DONTWAIT, &sockaddr_xdp) {
+        Ok(_) => Ok(()),
+        Err(errno) if is_transient_wakeup_error(errno) => Ok(()),
+        Err(errno) => Err(Error::Wakeup {
+            ring: RingKind::Tx,
+            errno,
+        }),
+    }
+}
+
+/// One iteration of the preferred busy polling contract, like the busy polling mode of `xdpsock`.
+///
+/// Both rings belong to the same socket.
+pub(crate) fn busy_poll<
+    'umem,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const RX_SIZE: usize,
+    const TX_SIZE: usize,
+>(
+    rx_ring: &RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+    tx_ring: &TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+) -> Result<(), Error> {
+    // The kernel ignores the need wakeup flags while busy polling, sendto processes pending TX descriptors and
+    // recvfrom runs the NAPI context of the queue.
+    if tx_ring.free_entries_for(TX_SIZE as u32) < TX_SIZE as u32 {
+        wake_tx(tx_ring.socket())?;
+    }
+    wake_rx(rx_ring.socket(), RingKind::Rx)
+}
+
+/// The kernel is busy or out of resources, the next wakeup picks up the work. Same set as `kick_tx` in libxdp.
+fn is_transient_wakeup_error(errno: Errno) -> bool {
+    matches!(
+        errno,
+        Errno::AGAIN | Errno::BUSY | Errno::NOBUFS | Errno::NETDOWN
+    )
+}
+
 pub type RxRing<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize> = Ring<
     'umem,
     Consumer,
     RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>,
     Marker,
-    CHUNK_SIZE,
-    RING_SIZE,
+    ConstSize<RING_SIZE>,
 >;
 pub type TxRing<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize> = Ring<
     'umem,
     Producer,
     RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>,
     Marker,
-    CHUNK_SIZE,
-    RING_SIZE,
+    ConstSize<RING_SIZE>,
 >;
 pub type CompletionRing<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize> = Ring<
     'umem,
     Consumer,
     FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>,
     Marker,
-    CHUNK_SIZE,
-    RING_SIZE,
+    ConstSize<RING_SIZE>,
 >;
 pub type FillRing<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize> = Ring<
     'umem,
     Producer,
     FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>,
     Marker,
-    CHUNK_SIZE,
-    RING_SIZE,
+    ConstSize<RING_SIZE>,
 >;
 
 impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>
 {
-    pub(crate) fn new(umem_memory: &'umem UmemMemory, socket: Arc<OwnedFd>) -> Result<Self, Error> {
-        set_xdp_rx_ring_size(socket.as_fd(), RING_SIZE as u32)?;
-        let offsets = xdp_mmap_offsets(socket.as_fd())?.rx;
-        RxRing::internal_new(offsets, XDP_PGOFF_RX_RING, umem_memory, socket)
+    pub(crate) fn new(
+        umem: &'umem Umem<Marker, CHUNK_SIZE>,
+        socket: Arc<OwnedFd>,
+    ) -> Result<Self, Error> {
+        RxRing::internal_new(
+            RingKind::Rx,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            ConstSize,
+        )
+    }
+
+    /// Pops all frames of the next packet, see [`Packet`].
+    ///
+    /// Returns `None` if the ring doesn't hold the complete packet, nothing is consumed in that case.
+    pub fn pop_packet(&mut self) -> Option<Packet<'umem, Marker, CHUNK_SIZE>> {
+        let frames = match self.packet_frames(self.filled_entries()) {
+            Some(frames) => frames,
+            // Only refresh the indices if the cached ones don't cover a complete packet.
+            None => self.packet_frames(self.filled_entries_for(self.filled_entries() + 1))?,
+        };
+        Some(Packet::new(self.peek(frames).collect()))
     }
 
+    /// Returns the number of frames of the packet at the consumer index if its last frame is within `filled`.
+    fn packet_frames(&self, filled: u32) -> Option<u32> {
+        let consumer = self.cached_consumer();
+        (0..filled)
+            .find(|&index| {
+                let desc = unsafe { self.read_descriptor(consumer.wrapping_add(index)) };
+                !desc.options.contains(XdpDescOptions::XDP_PKT_CONTD)
+            })
+            .map(|last| last + 1)
+    }
+}
+
+impl<'umem, Marker, C, Size> Ring<'umem, Consumer, RxTxDescriptor<'umem, Marker, C>, Marker, Size>
+where
+    C: Chunk + ?Sized,
+    Size: RingSize,
+{
     // Completion ring does not need a poke.
     // Tx rings sendmsg to start sending
     // XDP_USE_NEED_WAKEUP
@@ -122,24 +333,74 @@ impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     // FILL: recvmsg (because it actually wakes the RX ring)
     // TX: sendto
     // RX: recvmsg
-    pub fn poke(&self) {
+    pub fn poke(&self) -> Result<(), Error> {
         if let Some(flags) = self.flags()
             && flags == XdpRingFlags::XDP_RING_NEED_WAKEUP
         {
-            recvfrom::<_, &mut [u8; 0]>(self.socket.as_fd(), &mut [], RecvFlags::DONTWAIT).unwrap();
+            wake_rx(self.socket(), RingKind::Rx)?;
         }
+        Ok(())
+    }
+
+    /// Blocks until the ring has a filled entry or `timeout` elapsed, `None` waits indefinitely.
+    ///
+    /// Returns whether the ring has a filled entry. Polling the socket also wakes up the kernel if needed.
+    pub fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool, Error> {
+        if !self.is_empty() {
+            return Ok(true);
+        }
+        poll_socket(self.socket(), PollFlags::IN, timeout)?;
+        Ok(!self.is_empty())
     }
 }
 
 impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>
 {
-    pub(crate) fn new(umem_memory: &'umem UmemMemory, socket: Arc<OwnedFd>) -> Result<Self, Error> {
-        set_xdp_tx_ring_size(socket.as_fd(), RING_SIZE as u32)?;
-        let offsets = xdp_mmap_offsets(socket.as_fd())?.tx;
-        TxRing::internal_new(offsets, XDP_PGOFF_TX_RING, umem_memory, socket)
+    pub(crate) fn new(
+        umem: &'umem Umem<Marker, CHUNK_SIZE>,
+        socket: Arc<OwnedFd>,
+    ) -> Result<Self, Error> {
+        TxRing::internal_new(
+            RingKind::Tx,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            ConstSize,
+        )
     }
 
+    /// Pushes all frames of `packet` with a single producer update.
+    ///
+    /// The packet is returned if it has no frames or the ring doesn't have a free entry for each of its frames,
+    /// nothing is pushed in that case.
+    pub fn push_packet(
+        &mut self,
+        packet: Packet<'umem, Marker, CHUNK_SIZE>,
+    ) -> Result<(), Packet<'umem, Marker, CHUNK_SIZE>> {
+        if packet.frames().is_empty() {
+            return Err(packet);
+        }
+        let frames = packet.frames().len().try_into().unwrap_or(u32::MAX);
+        let mut batch = self.reserve(frames);
+        if batch.reserved() < frames {
+            return Err(packet);
+        }
+
+        for frame in packet.into_frames() {
+            batch
+                .write(frame)
+                .expect("batch has a slot reserved for every frame");
+        }
+        Ok(())
+    }
+}
+
+impl<'umem, Marker, C, Size> Ring<'umem, Producer, RxTxDescriptor<'umem, Marker, C>, Marker, Size>
+where
+    C: Chunk + ?Sized,
+    Size: RingSize,
+{
     // Completion ring does not need a poke
     // Tx rings sendmsg to start sending
     // XDP_USE_NEED_WAKEUP
@@ -147,45 +408,68 @@ impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     // FILL: recvmsg (because it actually wakes the RX ring)
     // TX: sendto
     // RX: recvmsg
-    pub fn poke(&self) {
+    pub fn poke(&self) -> Result<(), Error> {
         if let Some(flags) = self.flags()
             && flags == XdpRingFlags::XDP_RING_NEED_WAKEUP
         {
-            let sockaddr_xdp = SocketAddrXdp::new(
-                // Not used in sendmsg for XDP.
-                // https://github.com/torvalds/linux/blob/v6.10/net/xdp/xsk.c#L905-L948
-                SocketAddrXdpFlags::empty(),
-                // Not used in sendmsg for XDP.
-                // https://github.com/torvalds/linux/blob/v6.10/net/xdp/xsk.c#L905-L948
-                0,
-                // Not used in sendmsg for XDP.
-                // https://github.com/torvalds/linux/blob/v6.10/net/xdp/xsk.c#L905-L948
-                0,
-            );
-            sendto(self.socket.as_fd(), &[], SendFlags::DONTWAIT, &sockaddr_xdp).unwrap();
+            wake_tx(self.socket())?;
         }
+        Ok(())
+    }
+
+    /// Blocks until the ring has a free entry or `timeout` elapsed, `None` waits indefinitely.
+    ///
+    /// Returns whether the ring has a free entry. The socket only becomes writable once at most half of the ring is
+    /// filled. Polling the socket also wakes up the kernel to send pending frames if needed.
+    pub fn wait_writable(&self, timeout: Option<Duration>) -> Result<bool, Error> {
+        if !self.is_full() {
+            return Ok(true);
+        }
+        poll_socket(self.socket(), PollFlags::OUT, timeout)?;
+        Ok(!self.is_full())
     }
 }
 
 impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>
 {
-    pub(crate) fn new(umem_memory: &'umem UmemMemory, socket: Arc<OwnedFd>) -> Result<Self, Error> {
-        set_xdp_umem_completion_ring_size(socket.as_fd(), RING_SIZE as u32)?;
-        let offsets = xdp_mmap_offsets(socket.as_fd())?.cr;
-        CompletionRing::internal_new(offsets, XDP_UMEM_PGOFF_COMPLETION_RING, umem_memory, socket)
+    pub(crate) fn new(
+        umem: &'umem Umem<Marker, CHUNK_SIZE>,
+        socket: Arc<OwnedFd>,
+    ) -> Result<Self, Error> {
+        CompletionRing::internal_new(
+            RingKind::Completion,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            ConstSize,
+        )
     }
 }
 
 impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>
 {
-    pub(crate) fn new(umem_memory: &'umem UmemMemory, socket: Arc<OwnedFd>) -> Result<Self, Error> {
-        set_xdp_umem_fill_ring_size(socket.as_fd(), RING_SIZE as u32)?;
-        let offsets = xdp_mmap_offsets(socket.as_fd())?.fr;
-        FillRing::internal_new(offsets, XDP_UMEM_PGOFF_FILL_RING, umem_memory, socket)
+    pub(crate) fn new(
+        umem: &'umem Umem<Marker, CHUNK_SIZE>,
+        socket: Arc<OwnedFd>,
+    ) -> Result<Self, Error> {
+        FillRing::internal_new(
+            RingKind::Fill,
+            umem.socket(),
+            umem.memory(),
+            socket,
+            ConstSize,
+        )
     }
+}
 
+impl<'umem, Marker, C, Size>
+    Ring<'umem, Producer, FillCompDescriptor<'umem, Marker, C>, Marker, Size>
+where
+    C: Chunk + ?Sized,
+    Size: RingSize,
+{
     // Completion ring does not need a poke
     // Tx rings sendmsg to start sending
     // XDP_USE_NEED_WAKEUP
@@ -193,35 +477,55 @@ impl<'umem, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
     // FILL: recvmsg (because it actually wakes the RX ring)
     // TX: sendto
     // RX: recvmsg
-    pub fn poke(&self) {
+    pub fn poke(&self) -> Result<(), Error> {
         if let Some(flags) = self.flags()
             && flags == XdpRingFlags::XDP_RING_NEED_WAKEUP
         {
             // The wakeup flag in the fill ring means we need to wake up the RX ring:
             // https://github.com/torvalds/linux/commit/77cd0d7b3f257fd0e3096b4fdcff1a7d38e99e10
             // This means we can use recvfrom like in the RX ring.
-            recvfrom::<_, &mut [u8; 0]>(self.socket.as_fd(), &mut [], RecvFlags::DONTWAIT).unwrap();
+            wake_rx(self.socket(), RingKind::Fill)?;
         }
+        Ok(())
     }
 }
 
-impl<'umem, FrameDescriptor, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-    Ring<'umem, Producer, FrameDescriptor, Marker, CHUNK_SIZE, RING_SIZE>
+impl<'umem, FrameDescriptor, Marker, Size> Ring<'umem, Producer, FrameDescriptor, Marker, Size>
 where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE> + Debug,
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
 {
+    /// Number of free entries, refreshed from the shared memory if the cached indices show a full ring.
+    ///
+    /// This is a lower bound, the kernel might have consumed more entries since.
+    pub fn free_entries(&self) -> u32 {
+        self.free_entries_for(1)
+    }
+
+    /// Number of entries the kernel hasn't consumed yet, always refreshed from the shared memory.
+    pub fn filled_entries(&self) -> u32 {
+        self.refresh();
+        self.cached_filled_entries()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filled_entries() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free_entries() == 0
+    }
+
     pub fn push(&mut self, input: FrameDescriptor) -> Result<(), FrameDescriptor> {
         trace!("Pushing {:?}.", &input);
 
+        self.umem_memory.check_chunk(input.chunk());
         if !self.is_full() {
-            let producer = self.ring_memory.producer();
+            let producer = self.cached_producer();
 
-            unsafe {
-                self.ring_memory
-                    .write_descriptor(producer as usize, input.into_ring_repr())
-            };
+            unsafe { self.write_descriptor(producer, input.into_ring_repr()) };
 
-            self.ring_memory.set_producer(producer.wrapping_add(1));
+            self.set_producer(producer.wrapping_add(1));
 
             Ok(())
         } else {
@@ -229,24 +533,72 @@ where
             Err(input)
         }
     }
+
+    /// Reserves up to `count` slots on the ring.
+    ///
+    /// Fewer slots are reserved if the ring doesn't have `count` free entries.
+    pub fn reserve(
+        &mut self,
+        count: u32,
+    ) -> ProducerBatch<'_, 'umem, FrameDescriptor, Marker, Size> {
+        ProducerBatch::new(self, count)
+    }
+
+    /// Pushes descriptors from the end of `descriptors` with a single producer update.
+    ///
+    /// Returns the number of pushed descriptors, descriptors that didn't fit into the ring are left in
+    /// `descriptors`.
+    pub fn push_batch(&mut self, descriptors: &mut Vec<FrameDescriptor>) -> u32 {
+        let mut batch = self.reserve(descriptors.len().try_into().unwrap_or(u32::MAX));
+        let pushed = batch.reserved();
+
+        for descriptor in descriptors.drain(descriptors.len() - pushed as usize..) {
+            batch
+                .write(descriptor)
+                .expect("batch has a slot reserved for every drained descriptor");
+        }
+
+        pushed
+    }
 }
 
-impl<'umem, FrameDescriptor, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-    Ring<'umem, Consumer, FrameDescriptor, Marker, CHUNK_SIZE, RING_SIZE>
+impl<'umem, FrameDescriptor, Marker, Size> Ring<'umem, Consumer, FrameDescriptor, Marker, Size>
 where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE> + Debug,
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
 {
+    /// Number of filled entries, refreshed from the shared memory if the cached indices show an empty ring.
+    ///
+    /// This is a lower bound, the kernel might have produced more entries since.
+    pub fn filled_entries(&self) -> u32 {
+        self.filled_entries_for(1)
+    }
+
+    /// Number of entries the kernel can still produce, always refreshed from the shared memory.
+    pub fn free_entries(&self) -> u32 {
+        self.refresh();
+        self.cached_free_entries()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filled_entries() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free_entries() == 0
+    }
+
     pub fn pop(&mut self) -> Option<FrameDescriptor> {
         trace!("Popping.");
         if !self.is_empty() {
-            let consumer = self.ring_memory.consumer();
+            let consumer = self.cached_consumer();
 
             let desc = FrameDescriptor::from_ring_repr(
-                unsafe { self.ring_memory.read_descriptor(consumer as usize) },
+                unsafe { self.read_descriptor(consumer) },
                 self.umem_memory,
             );
 
-            self.ring_memory.set_consumer(consumer.wrapping_add(1));
+            self.set_consumer(consumer.wrapping_add(1));
 
             Some(desc)
         } else {
@@ -254,36 +606,50 @@ where
             None
         }
     }
+
+    /// Peeks up to `count` filled entries of the ring.
+    ///
+    /// Fewer entries are peeked if the ring doesn't have `count` filled entries.
+    pub fn peek(&mut self, count: u32) -> ConsumerBatch<'_, 'umem, FrameDescriptor, Marker, Size> {
+        ConsumerBatch::new(self, count)
+    }
+
+    /// Pops up to `count` descriptors into `descriptors` with a single consumer update.
+    ///
+    /// Returns the number of popped descriptors.
+    pub fn pop_batch(&mut self, count: u32, descriptors: &mut Vec<FrameDescriptor>) -> u32 {
+        let batch = self.peek(count);
+        let popped = batch.available();
+        descriptors.extend(batch);
+        popped
+    }
 }
 
-impl<'umem, RingType, FrameDescriptor, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-    Ring<'umem, RingType, FrameDescriptor, Marker, CHUNK_SIZE, RING_SIZE>
+impl<'umem, RingType, FrameDescriptor, Marker, Size>
+    Ring<'umem, RingType, FrameDescriptor, Marker, Size>
 where
-    FrameDescriptor: Descriptor<'umem, Marker, CHUNK_SIZE> + Debug,
+    FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+    Size: RingSize,
 {
-    fn internal_new(
-        offsets: XdpRingOffset,
-        mmap_offset: u64,
+    /// Sets the size of the ring on `socket` and maps it.
+    pub(crate) fn internal_new(
+        ring: RingKind,
+        umem_socket: &UmemSocket,
         umem_memory: &'umem UmemMemory,
         socket: Arc<OwnedFd>,
-    ) -> Result<Ring<'umem, RingType, FrameDescriptor, Marker, CHUNK_SIZE, RING_SIZE>, Error> {
-        const {
-            assert!(
-                RING_SIZE as u32 <= u32::MAX >> 1,
-                "RING_SIZE may not be larger than u32::MAX >> 1 to allow overflow"
-            );
-            assert!(
-                RING_SIZE.is_power_of_two(),
-                "RING_SIZE must be a power of two"
-            );
-        }
-
-        info!("Offsets: {offsets:?}");
+        size: Size,
+    ) -> Result<Ring<'umem, RingType, FrameDescriptor, Marker, Size>, Error> {
+        size.check(ring)?;
+        umem_socket.set_ring_size(&socket, ring, size.get())?;
 
-        let ring_memory = RingMemory::new(socket.as_fd(), mmap_offset, offsets)?;
+        let offsets = ring_offsets(socket.as_fd(), ring)?;
+        let ring_memory = RingMemory::new(socket.as_fd(), ring, offsets, size.get() as usize)?;
 
         Ok(Ring {
+            cached_producer: AtomicU32::new(ring_memory.producer()),
+            cached_consumer: AtomicU32::new(ring_memory.consumer()),
             ring_memory,
+            size,
             umem_memory,
             socket,
             ring_type: PhantomData,
@@ -291,6 +657,11 @@ where
         })
     }
 
+    /// Number of entries of the ring.
+    pub fn size(&self) -> u32 {
+        self.size.get()
+    }
+
     pub fn statistics(&self) -> Result<XdpStatistics, Error> {
         Ok(xdp_statistics(&self.socket)?)
     }
@@ -304,6 +675,10 @@ where
         Ok(option_flags.contains(XdpOptionsFlags::XDP_OPTIONS_ZEROCOPY))
     }
 
+    pub(crate) fn socket(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+
     pub fn flags(&self) -> Option<XdpRingFlags> {
         self.ring_memory.flags()
     }
@@ -316,24 +691,84 @@ where
         }
     }
 
-    pub fn free_entries(&self) -> u32 {
-        self.ring_memory
-            .consumer()
-            .wrapping_add(RING_SIZE as u32)
-            .wrapping_sub(self.ring_memory.producer())
+    /// Like `xsk_prod_nb_free`, only refreshes the cached indices if fewer than `wanted` entries are free.
+    pub(crate) fn free_entries_for(&self, wanted: u32) -> u32 {
+        let free = self.cached_free_entries();
+        if free >= wanted {
+            return free;
+        }
+        self.refresh();
+        self.cached_free_entries()
     }
 
-    pub fn filled_entries(&self) -> u32 {
-        self.ring_memory
-            .producer()
-            .wrapping_sub(self.ring_memory.consumer())
+    /// Like `xsk_cons_nb_avail`, only refreshes the cached indices if fewer than `wanted` entries are filled.
+    pub(crate) fn filled_entries_for(&self, wanted: u32) -> u32 {
+        let filled = self.cached_filled_entries();
+        if filled >= wanted {
+            return filled;
+        }
+        self.refresh();
+        self.cached_filled_entries()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.ring_memory.producer() == self.ring_memory.consumer()
+    fn cached_free_entries(&self) -> u32 {
+        self.cached_consumer()
+            .wrapping_add(self.size.get())
+            .wrapping_sub(self.cached_producer())
     }
 
-    pub fn is_full(&self) -> bool {
-        self.filled_entries() == RING_SIZE as u32
+    fn cached_filled_entries(&self) -> u32 {
+        self.cached_producer().wrapping_sub(self.cached_consumer())
+    }
+
+    /// Reloads both indices from the shared memory.
+    ///
+    /// Reloading the index owned by this side of the ring is a no-op value wise, but saves having to know which
+    /// side of the ring we are on.
+    fn refresh(&self) {
+        self.cached_producer
+            .store(self.ring_memory.producer(), Relaxed);
+        self.cached_consumer
+            .store(self.ring_memory.consumer(), Relaxed);
+    }
+
+    /// Reads the descriptor at the ring index `offset`, which wraps around at the ring size.
+    pub(crate) unsafe fn read_descriptor(
+        &self,
+        offset: u32,
+    ) -> FrameDescriptor::InRingDescriptorType {
+        unsafe { self.ring_memory.read_descriptor(self.wrap(offset)) }
+    }
+
+    /// Writes the descriptor at the ring index `offset`, which wraps around at the ring size.
+    pub(crate) unsafe fn write_descriptor(
+        &self,
+        offset: u32,
+        desc: FrameDescriptor::InRingDescriptorType,
+    ) {
+        unsafe { self.ring_memory.write_descriptor(self.wrap(offset), desc) }
+    }
+
+    /// The ring index overflows, the bits below the ring size cut off the overflow part.
+    fn wrap(&self, offset: u32) -> usize {
+        (offset & (self.size.get() - 1)) as usize
+    }
+
+    pub(crate) fn cached_producer(&self) -> u32 {
+        self.cached_producer.load(Relaxed)
+    }
+
+    pub(crate) fn cached_consumer(&self) -> u32 {
+        self.cached_consumer.load(Relaxed)
+    }
+
+    pub(crate) fn set_producer(&self, producer: u32) {
+        self.cached_producer.store(producer, Relaxed);
+        self.ring_memory.set_producer(producer);
+    }
+
+    pub(crate) fn set_consumer(&self, consumer: u32) {
+        self.cached_consumer.store(consumer, Relaxed);
+        self.ring_memory.set_consumer(consumer);
     }
 }
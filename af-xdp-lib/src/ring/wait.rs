@@ -0,0 +1,170 @@
+use crate::descriptor::Descriptor;
+use crate::error::Error;
+use crate::ring::{Ring, RingSize};
+use rustix::buffer::spare_capacity;
+use rustix::event::{PollFd, PollFlags, Timespec, epoll, poll};
+use rustix::io::Errno;
+use std::fmt::{Debug, Formatter};
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::time::Duration;
+
+/// Readiness to wait for on a socket.
+///
+/// For XDP sockets, readable means the RX ring has filled entries and writable that at most half of the TX ring is
+/// filled. The fill and completion ring don't have a readiness of their own.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Interest {
+    Readable,
+    Writable,
+    ReadableWritable,
+}
+
+impl Interest {
+    fn epoll_flags(self) -> epoll::EventFlags {
+        match self {
+            Interest::Readable => epoll::EventFlags::IN,
+            Interest::Writable => epoll::EventFlags::OUT,
+            Interest::ReadableWritable => epoll::EventFlags::IN | epoll::EventFlags::OUT,
+        }
+    }
+}
+
+/// A socket of a [`WaitSet`] that became ready.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct Ready {
+    /// The token the socket was registered with.
+    pub token: u64,
+    /// Also set if the socket reported an error or hang up, the next operation on it reports the cause.
+    pub readable: bool,
+    pub writable: bool,
+}
+
+/// An epoll set to block on several sockets at once, e.g. sockets that rarely receive anything.
+///
+/// Sockets are removed automatically once they are closed, which happens when all their rings are dropped. The UMEM
+/// keeps the socket it is registered with open, the rings of its first socket have to be removed with
+/// [`remove_ring`](WaitSet::remove_ring) before they are dropped to stop waiting on them. The sockets of all rings
+/// created from the same [`SocketBuilder`](crate::socket::SocketBuilder) call are the same, each socket can only be
+/// registered once.
+pub struct WaitSet {
+    epoll: OwnedFd,
+    events: Vec<epoll::Event>,
+}
+
+impl Debug for WaitSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WaitSet")
+            .field("epoll", &self.epoll)
+            .field("capacity", &self.events.capacity())
+            .finish()
+    }
+}
+
+impl WaitSet {
+    /// Creates an empty set, a single [`wait`](WaitSet::wait) returns up to `capacity` ready sockets.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn new(capacity: usize) -> Result<Self, Error> {
+        assert!(capacity > 0, "a wait set has to return at least one event");
+        let epoll =
+            epoll::create(epoll::CreateFlags::CLOEXEC).map_err(|errno| Error::Wait { errno })?;
+        Ok(Self {
+            epoll,
+            events: Vec::with_capacity(capacity),
+        })
+    }
+
+    /// Registers the socket of `ring`.
+    pub fn add_ring<'umem, RingType, FrameDescriptor, Marker, Size>(
+        &mut self,
+        ring: &Ring<'umem, RingType, FrameDescriptor, Marker, Size>,
+        token: u64,
+        interest: Interest,
+    ) -> Result<(), Error>
+    where
+        FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+        Size: RingSize,
+    {
+        self.add(ring.socket(), token, interest)
+    }
+
+    /// Registers any file descriptor, e.g. a control socket that isn't an XDP socket.
+    pub fn add(&mut self, fd: impl AsFd, token: u64, interest: Interest) -> Result<(), Error> {
+        epoll::add(
+            &self.epoll,
+            fd,
+            epoll::EventData::new_u64(token),
+            interest.epoll_flags(),
+        )
+        .map_err(|errno| Error::Wait { errno })
+    }
+
+    /// Removes the socket of `ring`.
+    pub fn remove_ring<'umem, RingType, FrameDescriptor, Marker, Size>(
+        &mut self,
+        ring: &Ring<'umem, RingType, FrameDescriptor, Marker, Size>,
+    ) -> Result<(), Error>
+    where
+        FrameDescriptor: Descriptor<'umem, Marker> + Debug,
+        Size: RingSize,
+    {
+        self.remove(ring.socket())
+    }
+
+    pub fn remove(&mut self, fd: impl AsFd) -> Result<(), Error> {
+        epoll::delete(&self.epoll, fd).map_err(|errno| Error::Wait { errno })
+    }
+
+    /// Blocks until at least one socket is ready or `timeout` elapsed, `None` waits indefinitely.
+    ///
+    /// Returns the ready sockets, which is empty on timeout or if a signal interrupted the wait.
+    pub fn wait(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<impl Iterator<Item = Ready> + '_, Error> {
+        self.events.clear();
+        match epoll::wait(
+            &self.epoll,
+            spare_capacity(&mut self.events),
+            timeout.map(timespec).as_ref(),
+        ) {
+            Ok(_) | Err(Errno::INTR) => {}
+            Err(errno) => return Err(Error::Wait { errno }),
+        }
+
+        Ok(self.events.iter().map(|event| {
+            let flags = event.flags;
+            Ready {
+                token: event.data.u64(),
+                readable: flags.intersects(
+                    epoll::EventFlags::IN | epoll::EventFlags::ERR | epoll::EventFlags::HUP,
+                ),
+                writable: flags.contains(epoll::EventFlags::OUT),
+            }
+        }))
+    }
+}
+
+/// Blocks until `socket` reports any of `flags` or `timeout` elapsed.
+///
+/// Polling an XDP socket also wakes up the kernel to process its rings if needed.
+pub(crate) fn poll_socket(
+    socket: BorrowedFd,
+    flags: PollFlags,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    let mut fds = [PollFd::new(&socket, flags)];
+    match poll(&mut fds, timeout.map(timespec).as_ref()) {
+        // The caller checks the ring either way, an interrupted wait is handled like a timeout.
+        Ok(_) | Err(Errno::INTR) => Ok(()),
+        Err(errno) => Err(Error::Wait { errno }),
+    }
+}
+
+fn timespec(timeout: Duration) -> Timespec {
+    // Before Linux 5.11, `epoll_wait` only takes an `int` of milliseconds and rejects longer timeouts.
+    let timeout = timeout.min(Duration::from_millis(i32::MAX as u64));
+    Timespec::try_from(timeout).expect("clamped timeout fits into a timespec")
+}
@@ -0,0 +1,415 @@
+use crate::error::Error;
+use crate::umem::registration::{BindLock, UmemSocket};
+use crate::umem::{DeviceId, QueueId};
+use crate::xsk_map::{Rings, XskMap, XskMapStorage};
+use rustix::io::Errno;
+use rustix::net::xdp::SocketAddrXdpFlags;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// How frames are moved between the driver and the UMEM.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum BindMode {
+    /// Zero-copy if the driver supports it, copy mode otherwise.
+    #[default]
+    Auto,
+    /// Always copy frames (`XDP_COPY`).
+    Copy,
+    /// Always use zero-copy (`XDP_ZEROCOPY`), binding fails if the driver doesn't support it.
+    ZeroCopy,
+}
+
+/// Which of the RX and TX ring a socket has.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Direction {
+    /// Receives and sends.
+    #[default]
+    RxTx,
+    /// Only receives, e.g. a sniffer. The socket has no TX ring.
+    Rx,
+    /// Only sends, e.g. a packet generator. The socket has no RX ring and isn't registered in the XSKMAP.
+    Tx,
+}
+
+/// Whether a socket has its own fill and completion ring or shares the ones of another socket bound to the same
+/// device and queue.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum FillComp {
+    /// Its own for the first socket of the device and queue, shared for later sockets.
+    #[default]
+    Auto,
+    /// Always its own, fails with [`Error::FillCompRingsInUse`] if the device and queue already has them.
+    Own,
+    /// Always shared, fails with [`Error::FillCompRingsRequired`] if the device and queue has none yet.
+    Shared,
+}
+
+/// Preferred busy polling options, see "Preferred busy polling" in the kernel's AF_XDP documentation.
+///
+/// Busy polling only happens while the application keeps issuing syscalls, see
+/// [`BusyPollRings::busy_poll`](crate::xsk_map::BusyPollRings::busy_poll). To keep the interrupts of the
+/// device disabled in between, `napi_defer_hard_irqs` and `gro_flush_timeout` of the device have to be set as well.
+/// Setting the options requires `CAP_NET_ADMIN` for values above the system defaults.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct BusyPoll {
+    /// How long a syscall busy polls for packets (`SO_BUSY_POLL`), with microsecond resolution.
+    pub timeout: Duration,
+    /// Maximum number of packets processed per busy poll (`SO_BUSY_POLL_BUDGET`), usually the batch size.
+    pub budget: u16,
+    /// Whether busy polling takes precedence over processing in softirq context (`SO_PREFER_BUSY_POLL`).
+    pub prefer: bool,
+}
+
+impl Default for BusyPoll {
+    /// The values of the example in the kernel documentation.
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_micros(20),
+            budget: 64,
+            prefer: true,
+        }
+    }
+}
+
+impl BusyPoll {
+    fn set(&self, socket: BorrowedFd) -> Result<(), Error> {
+        let timeout = self
+            .timeout
+            .as_micros()
+            .try_into()
+            .unwrap_or(libc::c_int::MAX);
+        set_socket_option(socket, libc::SO_PREFER_BUSY_POLL, self.prefer.into())?;
+        set_socket_option(socket, libc::SO_BUSY_POLL, timeout)?;
+        set_socket_option(socket, libc::SO_BUSY_POLL_BUDGET, self.budget.into())
+    }
+}
+
+// rustix doesn't expose the busy polling socket options yet.
+fn set_socket_option(
+    socket: BorrowedFd,
+    option: libc::c_int,
+    value: libc::c_int,
+) -> Result<(), Error> {
+    let result = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            option,
+            (&raw const value).cast(),
+            size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        let errno = Errno::from_io_error(&std::io::Error::last_os_error()).unwrap_or(Errno::IO);
+        Err(Error::BusyPoll { errno })
+    }
+}
+
+/// Options of a socket shared by the builders for the const and the dynamically sized rings.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub(crate) struct SocketOptions {
+    pub(crate) bind_mode: BindMode,
+    pub(crate) need_wakeup: bool,
+    pub(crate) multi_buffer: bool,
+    pub(crate) busy_poll: Option<BusyPoll>,
+    pub(crate) fill_comp: FillComp,
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self {
+            bind_mode: BindMode::default(),
+            need_wakeup: true,
+            multi_buffer: false,
+            busy_poll: None,
+            fill_comp: FillComp::default(),
+        }
+    }
+}
+
+impl SocketOptions {
+    /// Sets up the rings of a new socket of the UMEM bound to the device and queue.
+    ///
+    /// Uses `with_fill_comp` for sockets with their own fill and completion ring and `without_fill_comp` for sockets
+    /// sharing the ones of the device and queue. Both bind the socket with the lock they are passed, so no other
+    /// socket of the UMEM is set up in between.
+    pub(crate) fn build<'umem, Rings>(
+        &self,
+        umem: &'umem UmemSocket,
+        device_id: DeviceId,
+        queue_id: QueueId,
+        with_fill_comp: impl FnOnce(
+            Arc<OwnedFd>,
+            BindLock<'umem>,
+            SocketAddrXdpFlags,
+        ) -> Result<Rings, Error>,
+        without_fill_comp: impl FnOnce(
+            Arc<OwnedFd>,
+            BindLock<'umem>,
+            SocketAddrXdpFlags,
+        ) -> Result<Rings, Error>,
+    ) -> Result<Rings, Error> {
+        info!("rings");
+
+        let bind_lock = umem.lock_bindings();
+        let own_fill_comp = bind_lock.own_fill_comp(device_id, queue_id, self.fill_comp)?;
+        let socket = bind_lock.socket()?;
+        if let Some(umem_flags) = bind_lock.umem_socket_flags()
+            && !umem.is_umem_socket(&socket)
+        {
+            self.check_shared(umem_flags, device_id, queue_id)?;
+        }
+        self.set_busy_poll(socket.as_fd())?;
+
+        let result = if own_fill_comp {
+            with_fill_comp(socket, bind_lock, self.bind_flags())
+        } else {
+            without_fill_comp(socket, bind_lock, self.bind_flags())
+        };
+        match result {
+            // The kernel doesn't fall back to copy mode if zero-copy was requested explicitly.
+            Err(Error::Bind {
+                device_id,
+                queue_id,
+                errno: Errno::OPNOTSUPP,
+            }) if self.bind_mode == BindMode::ZeroCopy => Err(Error::ZeroCopyUnavailable {
+                device_id,
+                queue_id,
+                errno: Errno::OPNOTSUPP,
+            }),
+            result => result,
+        }
+    }
+
+    /// Fails if the options differ from the flags the UMEM socket was bound with, sockets sharing the UMEM can't set
+    /// their own. Any mode is accepted for [`BindMode::Auto`].
+    fn check_shared(
+        &self,
+        umem_flags: SocketAddrXdpFlags,
+        device_id: DeviceId,
+        queue_id: QueueId,
+    ) -> Result<(), Error> {
+        let bind_mode = if umem_flags.contains(SocketAddrXdpFlags::XDP_ZEROCOPY) {
+            BindMode::ZeroCopy
+        } else if umem_flags.contains(SocketAddrXdpFlags::XDP_COPY) {
+            BindMode::Copy
+        } else {
+            BindMode::Auto
+        };
+        let need_wakeup = umem_flags.contains(SocketAddrXdpFlags::XDP_USE_NEED_WAKEUP);
+        let multi_buffer = umem_flags.contains(SocketAddrXdpFlags::XDP_USE_SG);
+
+        let bind_mode_matches = self.bind_mode == BindMode::Auto
+            || bind_mode == BindMode::Auto
+            || self.bind_mode == bind_mode;
+        if bind_mode_matches && self.need_wakeup == need_wakeup && self.multi_buffer == multi_buffer
+        {
+            Ok(())
+        } else {
+            Err(Error::BindOptionsMismatch {
+                device_id,
+                queue_id,
+                bind_mode,
+                need_wakeup,
+                multi_buffer,
+            })
+        }
+    }
+
+    fn set_busy_poll(&self, socket: BorrowedFd) -> Result<(), Error> {
+        match &self.busy_poll {
+            Some(busy_poll) => busy_poll.set(socket),
+            None => Ok(()),
+        }
+    }
+
+    fn bind_flags(&self) -> SocketAddrXdpFlags {
+        let mut flags = match self.bind_mode {
+            BindMode::Auto => SocketAddrXdpFlags::empty(),
+            BindMode::Copy => SocketAddrXdpFlags::XDP_COPY,
+            BindMode::ZeroCopy => SocketAddrXdpFlags::XDP_ZEROCOPY,
+        };
+        if self.need_wakeup {
+            flags |= SocketAddrXdpFlags::XDP_USE_NEED_WAKEUP;
+        }
+        if self.multi_buffer {
+            flags |= SocketAddrXdpFlags::XDP_USE_SG;
+        }
+        flags
+    }
+}
+
+/// Builder for the rings of a socket bound to a queue and registered in the XSKMAP.
+///
+/// The bind options only apply to the first socket of a UMEM, the kernel doesn't allow setting them on sockets
+/// sharing the UMEM. Building those fails with [`Error::BindOptionsMismatch`] if their options differ.
+pub struct SocketBuilder<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize>
+where
+    XM: XskMap,
+{
+    xsk_map: &'xsk XskMapStorage<'umem, XM, Marker, CHUNK_SIZE>,
+    queue_id: QueueId,
+    map_index: u32,
+    direction: Direction,
+    options: SocketOptions,
+}
+
+impl<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize>
+    SocketBuilder<'umem, 'xsk, XM, Marker, CHUNK_SIZE>
+where
+    XM: XskMap,
+{
+    pub(crate) fn new(
+        xsk_map: &'xsk XskMapStorage<'umem, XM, Marker, CHUNK_SIZE>,
+        queue_id: QueueId,
+        map_index: u32,
+    ) -> Self {
+        Self {
+            xsk_map,
+            queue_id,
+            map_index,
+            direction: Direction::default(),
+            options: SocketOptions::default(),
+        }
+    }
+
+    /// Sets which of the RX and TX ring the socket has, [`Direction::RxTx`] by default.
+    ///
+    /// The map index is ignored for [`Direction::Tx`]. The first socket of a UMEM always has a fill and completion
+    /// ring, the kernel doesn't bind it otherwise.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets how frames are moved between the driver and the UMEM, [`BindMode::Auto`] by default.
+    pub fn bind_mode(mut self, bind_mode: BindMode) -> Self {
+        self.options.bind_mode = bind_mode;
+        self
+    }
+
+    /// Sets whether the kernel signals through the ring flags that it needs a wakeup (`XDP_USE_NEED_WAKEUP`),
+    /// enabled by default.
+    pub fn need_wakeup(mut self, need_wakeup: bool) -> Self {
+        self.options.need_wakeup = need_wakeup;
+        self
+    }
+
+    /// Sets whether packets may span multiple chunks (`XDP_USE_SG`), disabled by default.
+    ///
+    /// Without it, the kernel drops received packets larger than a chunk and TX descriptors with `XDP_PKT_CONTD`.
+    /// In native mode, the XDP program has to support fragments as well (`xdp.frags`). See
+    /// [`RxRing::pop_packet`](crate::ring::RxRing::pop_packet) and
+    /// [`TxRing::push_packet`](crate::ring::TxRing::push_packet).
+    pub fn multi_buffer(mut self, multi_buffer: bool) -> Self {
+        self.options.multi_buffer = multi_buffer;
+        self
+    }
+
+    /// Enables preferred busy polling, disabled by default.
+    ///
+    /// Unlike the bind options, this also applies to sockets sharing the UMEM.
+    pub fn busy_poll(mut self, busy_poll: BusyPoll) -> Self {
+        self.options.busy_poll = Some(busy_poll);
+        self
+    }
+
+    /// Sets whether the socket has its own fill and completion ring, [`FillComp::Auto`] by default.
+    pub fn fill_comp(mut self, fill_comp: FillComp) -> Self {
+        self.options.fill_comp = fill_comp;
+        self
+    }
+
+    /// Sets up the rings, binds the socket and registers it in the XSKMAP.
+    ///
+    /// Everything set up before a failing step is released again before the error is returned.
+    pub fn build<const RING_SIZE: usize>(
+        self,
+    ) -> Result<Rings<'umem, Marker, XM, CHUNK_SIZE, RING_SIZE>, Error> {
+        self.build_sized::<RING_SIZE, RING_SIZE, RING_SIZE, RING_SIZE>()
+    }
+
+    /// Like [`build`](Self::build), with a separate size for each ring.
+    ///
+    /// E.g. a fill ring larger than the RX ring keeps the kernel supplied with frames while the application
+    /// processes a batch. The sizes of the fill and completion ring are ignored if the socket shares the fill and
+    /// completion ring of another socket. Sockets with a single [`Direction`] only create the rings they need, the
+    /// size of the RX ring is ignored for [`Direction::Tx`] and the size of the TX ring for [`Direction::Rx`].
+    pub fn build_sized<
+        const FILL_SIZE: usize,
+        const COMPLETION_SIZE: usize,
+        const RX_SIZE: usize,
+        const TX_SIZE: usize,
+    >(
+        self,
+    ) -> Result<
+        Rings<'umem, Marker, XM, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, RX_SIZE, TX_SIZE>,
+        Error,
+    > {
+        let umem = self.xsk_map.umem().socket();
+        let device_id = self.xsk_map.net_device_id();
+        match self.direction {
+            Direction::RxTx => self.options.build(
+                umem,
+                device_id,
+                self.queue_id,
+                |socket, bind_lock, bind_flags| {
+                    self.xsk_map
+                        .fill_comp_rx_tx_rings(
+                            socket,
+                            bind_lock,
+                            self.queue_id,
+                            self.map_index,
+                            bind_flags,
+                        )
+                        .map(Rings::Four)
+                },
+                |socket, bind_lock, bind_flags| {
+                    self.xsk_map
+                        .rx_tx_rings(socket, bind_lock, self.queue_id, self.map_index, bind_flags)
+                        .map(Rings::Two)
+                },
+            ),
+            Direction::Rx => self.options.build(
+                umem,
+                device_id,
+                self.queue_id,
+                |socket, bind_lock, bind_flags| {
+                    self.xsk_map
+                        .fill_comp_rx_rings(
+                            socket,
+                            bind_lock,
+                            self.queue_id,
+                            self.map_index,
+                            bind_flags,
+                        )
+                        .map(Rings::FillCompRx)
+                },
+                |socket, bind_lock, bind_flags| {
+                    self.xsk_map
+                        .rx_rings(socket, bind_lock, self.queue_id, self.map_index, bind_flags)
+                        .map(Rings::Rx)
+                },
+            ),
+            Direction::Tx => self.options.build(
+                umem,
+                device_id,
+                self.queue_id,
+                |socket, bind_lock, bind_flags| {
+                    self.xsk_map
+                        .fill_comp_tx_rings(socket, bind_lock, self.queue_id, bind_flags)
+                        .map(Rings::FillCompTx)
+                },
+                |socket, bind_lock, bind_flags| {
+                    self.xsk_map
+                        .tx_rings(socket, bind_lock, self.queue_id, bind_flags)
+                        .map(Rings::Tx)
+                },
+            ),
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use crate::descriptor::{FillCompFrameDescriptor, RxTxFrameDescriptor};
+use crate::ring::{CompletionRing, FillRing, RxRing, TxRing};
+use std::any::type_name;
+use std::fmt::{Debug, Formatter};
+use std::ptr;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Mutex, MutexGuard};
+
+/// Free list for the frames of a UMEM, shared between threads.
+///
+/// Frame descriptors can't be cloned, a frame is either free in the pool, used by the application or on a ring,
+/// never in two places at once. Threads take frames through their own [`FrameCache`], which only locks the pool to
+/// exchange frames in batches.
+///
+/// The pool counts where its frames are. The kernel moves frames from the fill to the RX ring and from the TX to the
+/// completion ring on its own, so frames are counted per ring pair. Only frames moved through a [`FrameCache`]'s ring
+/// operations are counted on the rings, frames pushed to or popped from the rings directly count as in use. Mixing
+/// both keeps the counters consistent, e.g. a frame pushed to the fill ring directly and received through a cache
+/// stays counted as in use.
+pub struct FramePool<'umem, Marker, const CHUNK_SIZE: usize> {
+    free: Mutex<Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>>,
+    counters: Counters,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    free: AtomicUsize,
+    in_use: AtomicUsize,
+    fill_rx: AtomicUsize,
+    tx_completion: AtomicUsize,
+}
+
+impl Counters {
+    /// Frames `from` doesn't hold were moved through the rings directly and are still counted as in use.
+    fn moved(&self, from: &AtomicUsize, to: &AtomicUsize, count: usize) {
+        let missing = count - Self::take(from, count);
+        if missing > 0 && !ptr::eq(from, &self.in_use) {
+            Self::take(&self.in_use, missing);
+        }
+        to.fetch_add(count, Relaxed);
+    }
+
+    /// Subtracts up to `count` without wrapping, returns the subtracted amount.
+    fn take(counter: &AtomicUsize, count: usize) -> usize {
+        let previous = counter
+            .fetch_update(Relaxed, Relaxed, |value| Some(value.saturating_sub(count)))
+            .expect("the update always succeeds");
+        previous.min(count)
+    }
+}
+
+/// Snapshot of where the frames of a [`FramePool`] are.
+///
+/// The counters are updated independently, a snapshot taken while other threads move frames might not add up to the
+/// number of frames in the pool.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct FramePoolStats {
+    /// Frames in the pool or a cache.
+    pub free: usize,
+    /// Frames allocated or received and not yet freed or transmitted.
+    pub in_use: usize,
+    /// Frames pushed to the fill ring and not yet received from the RX ring.
+    pub fill_rx: usize,
+    /// Frames pushed to the TX ring and not yet popped from the completion ring.
+    pub tx_completion: usize,
+}
+
+impl<'umem, Marker, const CHUNK_SIZE: usize> Debug for FramePool<'umem, Marker, CHUNK_SIZE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("FramePool<{}>", type_name::<Marker>()))
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl<'umem, Marker, const CHUNK_SIZE: usize> FramePool<'umem, Marker, CHUNK_SIZE> {
+    /// Creates a pool of the frames of `descriptors`, usually all frames from
+    /// [`Umem::descriptors`](crate::umem::Umem::descriptors).
+    pub fn new(descriptors: Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>) -> Self {
+        let counters = Counters::default();
+        counters.free.store(descriptors.len(), Relaxed);
+        Self {
+            free: Mutex::new(descriptors),
+            counters,
+        }
+    }
+
+    /// Returns a cache for the calling thread, which exchanges frames with the pool in batches of up to `capacity`
+    /// frames.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn cache(&self, capacity: usize) -> FrameCache<'_, 'umem, Marker, CHUNK_SIZE> {
+        assert!(capacity > 0, "a frame cache has to hold at least one frame");
+        FrameCache {
+            pool: self,
+            frames: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns where the frames of the pool are, see [`FramePoolStats`].
+    pub fn stats(&self) -> FramePoolStats {
+        FramePoolStats {
+            free: self.counters.free.load(Relaxed),
+            in_use: self.counters.in_use.load(Relaxed),
+            fill_rx: self.counters.fill_rx.load(Relaxed),
+            tx_completion: self.counters.tx_completion.load(Relaxed),
+        }
+    }
+
+    /// Allocates a frame from the pool, bypassing the caches.
+    ///
+    /// Returns `None` if the pool is out of frames, frames held by caches aren't taken.
+    pub fn alloc(&self) -> Option<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>> {
+        let descriptor = self.lock().pop()?;
+        self.counters
+            .moved(&self.counters.free, &self.counters.in_use, 1);
+        Some(descriptor)
+    }
+
+    /// Allocates up to `count` frames into `descriptors`.
+    ///
+    /// Returns the number of allocated frames, fewer than `count` if the pool runs out of frames.
+    pub fn alloc_bulk(
+        &self,
+        count: usize,
+        descriptors: &mut Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> usize {
+        let allocated = self.take(count, descriptors);
+        self.counters
+            .moved(&self.counters.free, &self.counters.in_use, allocated);
+        allocated
+    }
+
+    /// Returns a frame to the pool, bypassing the caches.
+    pub fn free(&self, descriptor: impl Into<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>) {
+        self.lock().push(descriptor.into());
+        self.counters
+            .moved(&self.counters.in_use, &self.counters.free, 1);
+    }
+
+    /// Frees all frames of `descriptors`, leaving it empty.
+    pub fn free_bulk<D>(&self, descriptors: &mut Vec<D>)
+    where
+        D: Into<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    {
+        let freed = descriptors.len();
+        self.lock().extend(descriptors.drain(..).map(Into::into));
+        self.counters
+            .moved(&self.counters.in_use, &self.counters.free, freed);
+    }
+
+    /// Moves up to `count` free frames into `descriptors` without counting them as used.
+    fn take(
+        &self,
+        count: usize,
+        descriptors: &mut Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> usize {
+        let mut free = self.lock();
+        let taken = count.min(free.len());
+        let remaining = free.len() - taken;
+        descriptors.extend(free.drain(remaining..));
+        taken
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>> {
+        self.free.lock().unwrap()
+    }
+}
+
+/// Per-thread cache of free frames of a [`FramePool`], see [`FramePool::cache`].
+///
+/// Also moves frames between the cache and the rings, counting them on the rings of the pool. Returns its frames to
+/// the pool when dropped.
+pub struct FrameCache<'pool, 'umem, Marker, const CHUNK_SIZE: usize> {
+    pool: &'pool FramePool<'umem, Marker, CHUNK_SIZE>,
+    frames: Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    capacity: usize,
+}
+
+impl<'pool, 'umem, Marker, const CHUNK_SIZE: usize> Debug
+    for FrameCache<'pool, 'umem, Marker, CHUNK_SIZE>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("FrameCache<{}>", type_name::<Marker>()))
+            .field("frames", &self.frames.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl<'pool, 'umem, Marker, const CHUNK_SIZE: usize> FrameCache<'pool, 'umem, Marker, CHUNK_SIZE> {
+    /// Allocates a frame, refilling the cache from the pool if it's empty.
+    ///
+    /// Returns `None` if the cache and the pool are out of frames.
+    pub fn alloc(&mut self) -> Option<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>> {
+        self.refill(1);
+        let descriptor = self.frames.pop()?;
+        self.pool
+            .counters
+            .moved(&self.pool.counters.free, &self.pool.counters.in_use, 1);
+        Some(descriptor)
+    }
+
+    /// Allocates up to `count` frames into `descriptors`.
+    ///
+    /// Returns the number of allocated frames, fewer than `count` if the pool runs out of frames.
+    pub fn alloc_bulk(
+        &mut self,
+        count: usize,
+        descriptors: &mut Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> usize {
+        let allocated = self.take(count, descriptors);
+        self.pool.counters.moved(
+            &self.pool.counters.free,
+            &self.pool.counters.in_use,
+            allocated,
+        );
+        allocated
+    }
+
+    /// Frees a frame into the cache, returning frames to the pool once it exceeds its capacity.
+    pub fn free(
+        &mut self,
+        descriptor: impl Into<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) {
+        self.frames.push(descriptor.into());
+        self.pool
+            .counters
+            .moved(&self.pool.counters.in_use, &self.pool.counters.free, 1);
+        self.flush_excess();
+    }
+
+    /// Frees all frames of `descriptors`, leaving it empty.
+    pub fn free_bulk<D>(&mut self, descriptors: &mut Vec<D>)
+    where
+        D: Into<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    {
+        let freed = descriptors.len();
+        self.frames.extend(descriptors.drain(..).map(Into::into));
+        self.pool
+            .counters
+            .moved(&self.pool.counters.in_use, &self.pool.counters.free, freed);
+        self.flush_excess();
+    }
+
+    /// Pushes up to `count` free frames to the fill ring.
+    ///
+    /// Returns the number of pushed frames, fewer than `count` if the ring or the pool runs out of space or frames.
+    pub fn fill<const RING_SIZE: usize>(
+        &mut self,
+        fill_ring: &mut FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: usize,
+    ) -> u32 {
+        let mut descriptors = Vec::with_capacity(count.min(RING_SIZE));
+        self.take(count.min(RING_SIZE), &mut descriptors);
+        let pushed = fill_ring.push_batch(&mut descriptors);
+        // Frames that didn't fit into the ring stay free.
+        self.frames.append(&mut descriptors);
+        self.pool.counters.moved(
+            &self.pool.counters.free,
+            &self.pool.counters.fill_rx,
+            pushed as usize,
+        );
+        self.flush_excess();
+        pushed
+    }
+
+    /// Pops up to `count` received frames into `descriptors`, they are in use until freed or transmitted.
+    ///
+    /// Returns the number of received frames.
+    pub fn receive<const RING_SIZE: usize>(
+        &mut self,
+        rx_ring: &mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: u32,
+        descriptors: &mut Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> u32 {
+        let received = rx_ring.pop_batch(count, descriptors);
+        self.pool.counters.moved(
+            &self.pool.counters.fill_rx,
+            &self.pool.counters.in_use,
+            received as usize,
+        );
+        received
+    }
+
+    /// Pushes frames from the end of `descriptors` to the TX ring.
+    ///
+    /// Returns the number of pushed frames, frames that didn't fit into the ring are left in `descriptors`.
+    pub fn transmit<const RING_SIZE: usize>(
+        &mut self,
+        tx_ring: &mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        descriptors: &mut Vec<RxTxFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> u32 {
+        let transmitted = tx_ring.push_batch(descriptors);
+        self.pool.counters.moved(
+            &self.pool.counters.in_use,
+            &self.pool.counters.tx_completion,
+            transmitted as usize,
+        );
+        transmitted
+    }
+
+    /// Pops up to `count` completed frames from the completion ring into the cache.
+    ///
+    /// Returns the number of completed frames.
+    pub fn complete<const RING_SIZE: usize>(
+        &mut self,
+        completion_ring: &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        count: u32,
+    ) -> u32 {
+        let completed = completion_ring.pop_batch(count, &mut self.frames);
+        self.pool.counters.moved(
+            &self.pool.counters.tx_completion,
+            &self.pool.counters.free,
+            completed as usize,
+        );
+        self.flush_excess();
+        completed
+    }
+
+    /// Moves up to `count` free frames into `descriptors`, refilling the cache from the pool if needed.
+    ///
+    /// Frames beyond the capacity of the cache are taken from the pool directly.
+    fn take(
+        &mut self,
+        count: usize,
+        descriptors: &mut Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>>,
+    ) -> usize {
+        self.refill(count);
+        let taken = count.min(self.frames.len());
+        descriptors.extend(self.frames.drain(self.frames.len() - taken..));
+        if taken < count {
+            taken + self.pool.take(count - taken, descriptors)
+        } else {
+            taken
+        }
+    }
+
+    /// Takes frames from the pool up to the capacity if the cache holds fewer than `wanted`.
+    fn refill(&mut self, wanted: usize) {
+        if self.frames.len() < wanted {
+            let missing = self.capacity.saturating_sub(self.frames.len());
+            self.pool.take(missing, &mut self.frames);
+        }
+    }
+
+    /// Returns frames to the pool if the cache holds more than its capacity, keeping it half full.
+    fn flush_excess(&mut self) {
+        if self.frames.len() > self.capacity {
+            let excess = self.frames.split_off(self.capacity / 2);
+            self.pool.lock().extend(excess);
+        }
+    }
+}
+
+impl<'pool, 'umem, Marker, const CHUNK_SIZE: usize> Drop
+    for FrameCache<'pool, 'umem, Marker, CHUNK_SIZE>
+{
+    fn drop(&mut self) {
+        self.pool.lock().append(&mut self.frames);
+    }
+}
@@ -1,39 +1,66 @@
 use crate::error::Error;
+use crate::umem::RuntimeChecked;
 use std::any::TypeId;
 use std::collections::HashSet;
-use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{LazyLock, Mutex};
 
 static USED_MARKERS: LazyLock<Mutex<HashSet<TypeId>>> = LazyLock::new(Default::default);
 
-pub(crate) struct MarkerGuard<Marker>
-where
-    Marker: 'static,
-{
-    marker: PhantomData<Marker>,
+static NEXT_UMEM_ID: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) struct MarkerGuard {
+    // Unique for every UMEM of the process, also UMEMs sharing the marker type after one another.
+    umem_id: u64,
+    // The marker registered in `USED_MARKERS`, `None` if the UMEM isn't told apart by its marker type alone.
+    registered_marker: Option<TypeId>,
 }
 
-impl<Marker> MarkerGuard<Marker>
-where
-    Marker: 'static,
-{
-    pub(crate) fn new() -> Result<Self, Error> {
-        let mut used_markers = USED_MARKERS.lock().unwrap();
-        if !used_markers.insert(TypeId::of::<Marker>()) {
-            Err(Error::MarkerAlreadyUsed)
+impl MarkerGuard {
+    pub(crate) fn new<Marker>() -> Result<Self, Error>
+    where
+        Marker: 'static,
+    {
+        let registered_marker = if is_runtime_checked::<Marker>() {
+            None
         } else {
-            Ok(Self {
-                marker: PhantomData,
-            })
+            let mut used_markers = USED_MARKERS.lock().unwrap();
+            if !used_markers.insert(TypeId::of::<Marker>()) {
+                return Err(Error::MarkerAlreadyUsed);
+            }
+            Some(TypeId::of::<Marker>())
+        };
+        Ok(Self {
+            umem_id: NEXT_UMEM_ID.fetch_add(1, Ordering::Relaxed),
+            registered_marker,
+        })
+    }
+
+    /// Guard of a UMEM with a [`Brand`](crate::umem::Brand), the brand is unique without registering it.
+    pub(crate) fn branded() -> Self {
+        Self {
+            umem_id: NEXT_UMEM_ID.fetch_add(1, Ordering::Relaxed),
+            registered_marker: None,
         }
     }
+
+    pub(crate) fn umem_id(&self) -> u64 {
+        self.umem_id
+    }
 }
 
-impl<Marker> Drop for MarkerGuard<Marker>
+impl Drop for MarkerGuard {
+    fn drop(&mut self) {
+        if let Some(marker) = self.registered_marker {
+            USED_MARKERS.lock().unwrap().remove(&marker);
+        }
+    }
+}
+
+/// Whether any number of UMEMs can use `Marker` at the same time, see [`RuntimeChecked`].
+pub(crate) fn is_runtime_checked<Marker>() -> bool
 where
     Marker: 'static,
 {
-    fn drop(&mut self) {
-        USED_MARKERS.lock().unwrap().remove(&TypeId::of::<Marker>());
-    }
+    TypeId::of::<Marker>() == TypeId::of::<RuntimeChecked>()
 }
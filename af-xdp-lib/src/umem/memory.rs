@@ -1,20 +1,66 @@
+use crate::descriptor::sealed::SealedDescriptorImpl;
+use crate::descriptor::{Chunk, FillCompDescriptor};
+use crate::error::Error;
+use crate::mmap::non_null_mapping;
+use crate::umem::region::UmemRegion;
+use crate::umem::{ChunkMode, MemoryBacking};
+use rustix::io::Errno;
+use rustix::mm::{MapFlags, ProtFlags, mmap_anonymous, munmap};
 use rustix::param::page_size;
 use std::alloc::{Layout, alloc_zeroed, dealloc, handle_alloc_error};
 use std::ptr::NonNull;
+use tracing::warn;
 
 #[derive(Debug)]
 pub(crate) struct UmemMemory {
     memory: NonNull<u8>,
     number_of_chunks: usize,
     chunk_size: usize,
+    chunk_mode: ChunkMode,
+    backing: MemoryBacking,
+    // Caller-provided memory `memory` points into, `None` if the memory was allocated here.
+    region: Option<Box<dyn UmemRegion>>,
+    // Whether descriptors pushed onto rings are checked to point into `memory`, see `RuntimeChecked`.
+    check_chunks: bool,
 }
+
+// Safety:
+// UmemMemory is not Send and Sync because NonNull has no guarantees to make it Send and Sync.
+// The pointer is never altered, the chunks it points to are only accessed through the descriptor handed out for them.
+unsafe impl Send for UmemMemory {}
+unsafe impl Sync for UmemMemory {}
+
 impl UmemMemory {
-    pub(crate) fn new(number_of_chunks: usize, chunk_size: usize) -> Self {
-        let layout = Layout::from_size_align(
-            Self::allocation_length_internal(number_of_chunks, chunk_size),
-            page_size(),
-        )
-        .unwrap();
+    /// Allocates the memory with the requested `backing`, falling back to regular pages if the hugepages can't be
+    /// mapped, e.g. because none are reserved.
+    pub(crate) fn new(
+        number_of_chunks: usize,
+        chunk_size: usize,
+        chunk_mode: ChunkMode,
+        backing: MemoryBacking,
+    ) -> Self {
+        let allocation_length = Self::allocation_length_internal(number_of_chunks, chunk_size);
+
+        if let Some(hugepage_flags) = backing.hugepage_flags() {
+            match Self::map_hugepages(allocation_length, backing, hugepage_flags) {
+                Ok(memory) => {
+                    return Self {
+                        memory,
+                        number_of_chunks,
+                        chunk_size,
+                        chunk_mode,
+                        backing,
+                        region: None,
+                        check_chunks: false,
+                    };
+                }
+                Err(errno) => {
+                    warn!("Failed to map {backing:?} UMEM, falling back to regular pages: {errno}");
+                }
+            }
+        }
+
+        let layout = Layout::from_size_align(allocation_length, page_size()).unwrap();
         let umem_region = unsafe { alloc_zeroed(layout) };
         if umem_region.is_null() {
             handle_alloc_error(layout);
@@ -25,7 +71,62 @@ impl UmemMemory {
             memory,
             number_of_chunks,
             chunk_size,
+            chunk_mode,
+            backing: MemoryBacking::Regular,
+            region: None,
+            check_chunks: false,
+        }
+    }
+
+    /// Uses the memory of `region`, which has to be page-aligned and large enough to hold all chunks.
+    pub(crate) fn from_region(
+        region: Box<dyn UmemRegion>,
+        number_of_chunks: usize,
+        chunk_size: usize,
+        chunk_mode: ChunkMode,
+    ) -> Result<Self, Error> {
+        let required_len = Self::allocation_length_internal(number_of_chunks, chunk_size);
+        let addr = region.as_ptr().as_ptr() as usize;
+        if !addr.is_multiple_of(page_size()) || region.len() < required_len {
+            return Err(Error::InvalidUmemRegion {
+                addr,
+                len: region.len(),
+                required_len,
+            });
         }
+
+        Ok(Self {
+            memory: region.as_ptr(),
+            number_of_chunks,
+            chunk_size,
+            chunk_mode,
+            // Not used for caller-provided memory.
+            backing: MemoryBacking::Regular,
+            region: Some(region),
+            check_chunks: false,
+        })
+    }
+
+    fn map_hugepages(
+        allocation_length: usize,
+        backing: MemoryBacking,
+        hugepage_flags: MapFlags,
+    ) -> Result<NonNull<u8>, Errno> {
+        let mapping_length = Self::mapping_length(allocation_length, backing);
+        let memory = unsafe {
+            mmap_anonymous(
+                std::ptr::null_mut(),
+                mapping_length,
+                ProtFlags::READ | ProtFlags::WRITE,
+                MapFlags::PRIVATE | hugepage_flags,
+            )
+        }?;
+        non_null_mapping(memory, mapping_length).map(NonNull::cast)
+    }
+
+    /// Hugepage mappings have to cover whole hugepages.
+    fn mapping_length(allocation_length: usize, backing: MemoryBacking) -> usize {
+        allocation_length.next_multiple_of(backing.page_size())
     }
 
     pub(crate) fn allocation_length(&self) -> usize {
@@ -39,12 +140,64 @@ impl UmemMemory {
     pub(crate) fn memory(&self) -> NonNull<u8> {
         self.memory
     }
+
+    pub(crate) fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    pub(crate) fn chunk_mode(&self) -> ChunkMode {
+        self.chunk_mode
+    }
+
+    /// `None` for caller-provided memory.
+    pub(crate) fn backing(&self) -> Option<MemoryBacking> {
+        self.region.is_none().then_some(self.backing)
+    }
+
+    pub(crate) fn set_check_chunks(&mut self, check_chunks: bool) {
+        self.check_chunks = check_chunks;
+    }
+
+    /// Returns a descriptor for each chunk.
+    pub(crate) fn descriptors<Marker, C>(&self) -> Vec<FillCompDescriptor<'_, Marker, C>>
+    where
+        C: Chunk + ?Sized,
+    {
+        (0..self.number_of_chunks)
+            .map(|chunk_index| {
+                let addr = (chunk_index * self.chunk_size) as u64;
+                FillCompDescriptor::from_ring_repr(addr, self)
+            })
+            .rev()
+            .collect()
+    }
+
+    /// Panics if `chunk` doesn't point into this memory.
+    ///
+    /// Only checked for UMEMs with the [`RuntimeChecked`](crate::umem::RuntimeChecked) marker, the marker type
+    /// keeps descriptors of other UMEMs off the rings otherwise.
+    pub(crate) fn check_chunk(&self, chunk: *const u8) {
+        if self.check_chunks {
+            let start = self.memory.as_ptr() as usize;
+            assert!(
+                (start..start + self.allocation_length()).contains(&(chunk as usize)),
+                "descriptor of another UMEM"
+            );
+        }
+    }
 }
 
 impl Drop for UmemMemory {
     fn drop(&mut self) {
-        let layout = Layout::from_size_align(self.allocation_length(), page_size())
-            .expect("Size and page size should not have changed since new()");
-        unsafe { dealloc(self.memory.as_ptr(), layout) };
+        if self.region.is_some() {
+            // The region releases its memory itself.
+        } else if self.backing.hugepage_flags().is_some() {
+            let mapping_length = Self::mapping_length(self.allocation_length(), self.backing);
+            unsafe { munmap(self.memory.as_ptr().cast(), mapping_length) }.unwrap();
+        } else {
+            let layout = Layout::from_size_align(self.allocation_length(), page_size())
+                .expect("Size and page size should not have changed since new()");
+            unsafe { dealloc(self.memory.as_ptr(), layout) };
+        }
     }
 }
@@ -1,23 +1,21 @@
-mod maker_guard;
+pub mod frame_pool;
+pub(crate) mod maker_guard;
 pub(crate) mod memory;
+pub mod region;
+pub(crate) mod registration;
 
 use crate::descriptor::FillCompFrameDescriptor;
-use crate::descriptor::sealed::SealedDescriptorImpl;
 use crate::error::Error;
-use crate::umem::maker_guard::MarkerGuard;
+use crate::umem::maker_guard::{MarkerGuard, is_runtime_checked};
 use crate::umem::memory::UmemMemory;
-use rustix::net::sockopt::set_xdp_umem_reg;
-use rustix::net::xdp::{
-    SocketAddrXdp, SocketAddrXdpFlags, SocketAddrXdpWithSharedUmem, XdpUmemReg, XdpUmemRegFlags,
-};
-use rustix::net::{AddressFamily, SocketFlags, SocketType, bind, socket_with};
+use crate::umem::region::UmemRegion;
+use crate::umem::registration::{UmemConfig, UmemSocket};
+use rustix::mm::MapFlags;
+use rustix::net::xdp::XdpUmemRegFlags;
+use rustix::param::page_size;
 use std::any::type_name;
 use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
-use std::os::fd::{AsFd, OwnedFd};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::info;
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
 pub struct DeviceId(pub u32);
@@ -32,138 +30,355 @@ pub struct QueueId(pub u32);
 /// [mailing_list_post]: https://lore.kernel.org/xdp-newbies/CALDO+Sb00zQKuGKP43q-WEVXntMhmL+y8RN-_NTB879HxYbfTA@mail.gmail.com/
 pub const XDP_FRAME_DRIVER_HEADROOM: usize = 256;
 
-pub struct DescriptorsToken<Marker>(PhantomData<Marker>);
+/// Bit position of the data offset in descriptor addresses in unaligned chunk mode (`XSK_UNALIGNED_BUF_OFFSET_SHIFT`).
+const UNALIGNED_OFFSET_SHIFT: u64 = 48;
+
+/// Bits of descriptor addresses holding the chunk address in unaligned chunk mode (`XSK_UNALIGNED_BUF_ADDR_MASK`).
+const UNALIGNED_ADDR_MASK: u64 = (1 << UNALIGNED_OFFSET_SHIFT) - 1;
+
+/// How the chunks of a UMEM are laid out.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum ChunkMode {
+    /// Chunks start at multiples of the chunk size, which has to be a power of two.
+    #[default]
+    Aligned,
+    /// Chunks may start at any address and have any size (`XDP_UMEM_UNALIGNED_CHUNK_FLAG`).
+    ///
+    /// The kernel stores the offset of the data from the chunk start in the upper 16 bits of descriptor addresses.
+    /// In zero-copy mode, a chunk must not cross a page boundary unless the pages are physically contiguous, e.g.
+    /// because they're hugepages.
+    Unaligned,
+}
+
+impl ChunkMode {
+    /// Returns the address of the chunk `addr` points into.
+    pub(crate) fn chunk_addr(self, addr: u64, chunk_size: usize) -> u64 {
+        match self {
+            ChunkMode::Aligned => addr & !(chunk_size as u64 - 1),
+            ChunkMode::Unaligned => addr & UNALIGNED_ADDR_MASK,
+        }
+    }
+
+    /// Returns the offset of `addr` from the start of the chunk it points into.
+    pub(crate) fn data_offset(self, addr: u64, chunk_size: usize) -> u64 {
+        match self {
+            ChunkMode::Aligned => addr - self.chunk_addr(addr, chunk_size),
+            ChunkMode::Unaligned => addr >> UNALIGNED_OFFSET_SHIFT,
+        }
+    }
+
+    /// Returns the descriptor address pointing `data_offset` bytes into the chunk at `chunk_addr`.
+    pub(crate) fn addr(self, chunk_addr: u64, data_offset: u64) -> u64 {
+        match self {
+            ChunkMode::Aligned => chunk_addr + data_offset,
+            ChunkMode::Unaligned => chunk_addr | (data_offset << UNALIGNED_OFFSET_SHIFT),
+        }
+    }
+
+    fn umem_reg_flags(self) -> XdpUmemRegFlags {
+        match self {
+            ChunkMode::Aligned => XdpUmemRegFlags::empty(),
+            ChunkMode::Unaligned => XdpUmemRegFlags::XDP_UMEM_UNALIGNED_CHUNK_FLAG,
+        }
+    }
+}
+
+/// Pages backing the memory of a UMEM.
+///
+/// Hugepages reduce TLB misses when accessing a large UMEM in the data path. They have to be reserved beforehand, e.g.
+/// through `/proc/sys/vm/nr_hugepages` for 2 MiB hugepages.
+#[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum MemoryBacking {
+    /// Regular pages.
+    #[default]
+    Regular,
+    /// 2 MiB hugepages (`MAP_HUGETLB | MAP_HUGE_2MB`).
+    HugePages2M,
+    /// 1 GiB hugepages (`MAP_HUGETLB | MAP_HUGE_1GB`).
+    HugePages1G,
+}
+
+impl MemoryBacking {
+    pub(crate) fn hugepage_flags(self) -> Option<MapFlags> {
+        match self {
+            MemoryBacking::Regular => None,
+            MemoryBacking::HugePages2M => Some(MapFlags::HUGETLB | MapFlags::HUGE_2MB),
+            MemoryBacking::HugePages1G => Some(MapFlags::HUGETLB | MapFlags::HUGE_1GB),
+        }
+    }
+
+    pub(crate) fn page_size(self) -> usize {
+        match self {
+            MemoryBacking::Regular => page_size(),
+            MemoryBacking::HugePages2M => 2 * 1024 * 1024,
+            MemoryBacking::HugePages1G => 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// A marker any number of live UMEMs can use at the same time, e.g. to create a UMEM per queue in a loop.
+///
+/// UMEMs with a marker of their own are told apart by the type of their descriptors. For UMEMs with this marker,
+/// the rings instead check that pushed descriptors point into their UMEM and panic otherwise.
+#[derive(Debug)]
+pub struct RuntimeChecked;
+
+/// A marker unique to the UMEM created by [`UmemBuilder::with`] or
+/// [`DynUmemBuilder::with`](crate::dynamic::umem::DynUmemBuilder::with), without registering it at runtime.
+///
+/// `'id` is invariant and only lives inside the closure, so no two UMEMs share a brand and descriptors of one
+/// UMEM can't be pushed onto the rings of another one at compile time.
+///
+/// Pushing a descriptor of another branded UMEM doesn't compile:
+///
+/// ```compile_fail
+/// use af_xdp_lib::umem::{Brand, DeviceId, QueueId, Umem};
+/// use af_xdp_lib::xsk_map::{Rings, XskMap, XskMapStorage};
+///
+/// fn push_other(xsk_map: impl XskMap, device_id: DeviceId) {
+///     Umem::<Brand, 4096>::builder()
+///         .with(64, |umem, _descriptors_token| {
+///             Umem::<Brand, 4096>::builder()
+///                 .with(64, |other_umem, other_token| {
+///                     let xsk_map = XskMapStorage::new(xsk_map, device_id, &umem);
+///                     let mut descriptors = other_umem.descriptors(other_token);
+///                     if let Rings::Four(mut rings) = xsk_map.rings::<32>(QueueId(0), 0) {
+///                         rings.fill_ring().push(descriptors.pop().unwrap()).unwrap();
+///                     }
+///                 })
+///                 .unwrap();
+///         })
+///         .unwrap();
+/// }
+/// ```
+///
+/// Neither do descriptors leaving the closure, even if the UMEM itself is leaked:
+///
+/// ```compile_fail
+/// use af_xdp_lib::umem::{Brand, Umem};
+///
+/// let descriptors = Umem::<Brand, 4096>::builder()
+///     .with(64, |umem, descriptors_token| {
+///         let umem = Box::leak(Box::new(umem));
+///         umem.descriptors(descriptors_token)
+///     })
+///     .unwrap();
+/// ```
+pub struct Brand<'id>(PhantomData<fn(&'id ()) -> &'id ()>);
+
+impl Debug for Brand<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Brand")
+    }
+}
+
+/// Hands out the descriptors of the UMEM it was created with, once.
+pub struct DescriptorsToken<Marker> {
+    umem_id: u64,
+    marker: PhantomData<fn(Marker)>,
+}
 
 impl<Marker> Debug for DescriptorsToken<Marker> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(&format!("DescriptorsToken<{}>", type_name::<Marker>()))
+            .field("umem_id", &self.umem_id)
             .finish()
     }
 }
 
-pub struct Umem<Marker, const CHUNK_SIZE: usize>
-where
-    Marker: 'static,
-{
+impl<Marker> DescriptorsToken<Marker> {
+    pub(crate) fn new(marker_guard: &MarkerGuard) -> Self {
+        Self {
+            umem_id: marker_guard.umem_id(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Consumes the token, panics if it wasn't created with the UMEM of `marker_guard`.
+    pub(crate) fn redeem(self, marker_guard: &MarkerGuard) {
+        assert_eq!(
+            self.umem_id,
+            marker_guard.umem_id(),
+            "descriptors token of another UMEM"
+        );
+    }
+}
+
+pub struct Umem<Marker, const CHUNK_SIZE: usize> {
+    // Declared before `memory` to close the socket the memory is registered with before the memory is released.
+    socket: UmemSocket,
     memory: UmemMemory,
-    initial_rings_given_out: AtomicBool,
     number_of_chunks: usize,
-    socket: Arc<OwnedFd>,
-    _marker_guard: MarkerGuard<Marker>,
+    marker_guard: MarkerGuard,
+    marker: PhantomData<fn(Marker)>,
 }
 
 impl<Marker, const CHUNK_SIZE: usize> Debug for Umem<Marker, CHUNK_SIZE> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(&format!("Umem<{}>", type_name::<Marker>()))
             .field("memory", &self.memory)
-            .field("initial_rings_given_out", &self.initial_rings_given_out)
+            .field(
+                "initial_rings_given_out",
+                &self.socket.initial_rings_given_out(),
+            )
             .field("number_of_chunks", &self.number_of_chunks)
             .finish()
     }
 }
 
-unsafe impl<Marker, const CHUNK_SIZE: usize> Send for Umem<Marker, CHUNK_SIZE> {}
-unsafe impl<Marker, const CHUNK_SIZE: usize> Sync for Umem<Marker, CHUNK_SIZE> {}
+/// Builder for a [`Umem`].
+pub struct UmemBuilder<Marker, const CHUNK_SIZE: usize> {
+    config: UmemConfig,
+    marker: PhantomData<fn(Marker)>,
+}
+
+impl<Marker, const CHUNK_SIZE: usize> Debug for UmemBuilder<Marker, CHUNK_SIZE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.config
+            .debug(f, &format!("UmemBuilder<{}>", type_name::<Marker>()))
+    }
+}
+
+impl<Marker, const CHUNK_SIZE: usize> UmemBuilder<Marker, CHUNK_SIZE> {
+    /// Sets the headroom the kernel leaves free in front of received data, 0 by default.
+    pub fn headroom(mut self, headroom: u32) -> Self {
+        self.config.headroom = headroom;
+        self
+    }
+
+    /// Sets how the chunks are laid out, [`ChunkMode::Aligned`] by default.
+    pub fn chunk_mode(mut self, chunk_mode: ChunkMode) -> Self {
+        self.config.chunk_mode = chunk_mode;
+        self
+    }
+
+    /// Sets the pages the memory is allocated from, [`MemoryBacking::Regular`] by default.
+    ///
+    /// Falls back to regular pages if the requested hugepages aren't available, see [`Umem::memory_backing`] for the
+    /// backing actually used.
+    pub fn memory_backing(mut self, memory_backing: MemoryBacking) -> Self {
+        self.config.memory_backing = memory_backing;
+        self
+    }
+
+    /// Registers memory owned by the caller as UMEM instead of allocating it, overriding the memory backing.
+    ///
+    /// The region has to hold at least the chunks passed to [`build`](UmemBuilder::build).
+    pub fn memory_region(mut self, memory_region: impl UmemRegion + 'static) -> Self {
+        self.config.memory_region = Some(Box::new(memory_region));
+        self
+    }
+
+    /// Sets whether TX frames can carry [`TxMetadata`] in front of their data, disabled by default.
+    ///
+    /// Requires Linux 6.11 or later. The metadata isn't part of the headroom, a TX frame's data has to start at
+    /// least `size_of::<TxMetadata>()` bytes after the chunk start.
+    ///
+    /// [`TxMetadata`]: crate::descriptor::tx_metadata::TxMetadata
+    pub fn tx_metadata(mut self, tx_metadata: bool) -> Self {
+        self.config.tx_metadata = tx_metadata;
+        self
+    }
+
+    /// Allocates the memory for `number_of_chunks` chunks and registers it as UMEM.
+    pub fn build(
+        self,
+        number_of_chunks: usize,
+    ) -> Result<(Umem<Marker, CHUNK_SIZE>, DescriptorsToken<Marker>), Error>
+    where
+        Marker: 'static,
+    {
+        let marker_guard = MarkerGuard::new::<Marker>()?;
+        Umem::register(
+            self.config,
+            number_of_chunks,
+            marker_guard,
+            is_runtime_checked::<Marker>(),
+        )
+    }
+}
+
+impl<const CHUNK_SIZE: usize> UmemBuilder<Brand<'_>, CHUNK_SIZE> {
+    /// Allocates the memory for `number_of_chunks` chunks, registers it as UMEM and passes it to `f`.
+    ///
+    /// The UMEM gets a [`Brand`] of its own, which doesn't take the global marker lock and can't be used by any
+    /// other UMEM. The UMEM, its rings and descriptors can't leave `f`.
+    pub fn with<R>(
+        self,
+        number_of_chunks: usize,
+        f: impl for<'id> FnOnce(Umem<Brand<'id>, CHUNK_SIZE>, DescriptorsToken<Brand<'id>>) -> R,
+    ) -> Result<R, Error> {
+        let (umem, descriptors_token) =
+            Umem::register(self.config, number_of_chunks, MarkerGuard::branded(), false)?;
+        Ok(f(umem, descriptors_token))
+    }
+}
 
 impl<Marker, const CHUNK_SIZE: usize> Umem<Marker, CHUNK_SIZE> {
     pub fn new(
         headroom: u32,
         number_of_chunks: usize,
-    ) -> Result<(Self, DescriptorsToken<Marker>), Error> {
-        let marker_guard = MarkerGuard::new()?;
-        info!("Allocate memory.");
+    ) -> Result<(Self, DescriptorsToken<Marker>), Error>
+    where
+        Marker: 'static,
+    {
+        Self::builder().headroom(headroom).build(number_of_chunks)
+    }
 
-        let memory = UmemMemory::new(number_of_chunks, CHUNK_SIZE);
+    pub fn builder() -> UmemBuilder<Marker, CHUNK_SIZE> {
+        UmemBuilder {
+            config: UmemConfig::default(),
+            marker: PhantomData,
+        }
+    }
 
-        let socket = Arc::new(socket_with(
-            AddressFamily::XDP,
-            SocketType::RAW,
-            SocketFlags::CLOEXEC,
-            None,
-        )?);
+    fn register(
+        config: UmemConfig,
+        number_of_chunks: usize,
+        marker_guard: MarkerGuard,
+        check_chunks: bool,
+    ) -> Result<(Self, DescriptorsToken<Marker>), Error> {
+        let (socket, mut memory) = config.register(CHUNK_SIZE, number_of_chunks)?;
+        memory.set_check_chunks(check_chunks);
 
-        let umem = Self {
-            memory,
-            initial_rings_given_out: AtomicBool::new(false),
+        let descriptors_token = DescriptorsToken::new(&marker_guard);
+        let umem = Umem {
             socket,
+            memory,
             number_of_chunks,
-            _marker_guard: marker_guard,
+            marker_guard,
+            marker: PhantomData,
         };
 
-        info!("Registering UMEM.");
-
-        let umem_reg = XdpUmemReg {
-            addr: umem.memory.memory().as_ptr() as u64,
-            len: umem.memory.allocation_length() as u64,
-            chunk_size: CHUNK_SIZE as u32,
-            headroom,
-            flags: XdpUmemRegFlags::empty(),
-            tx_metadata_len: 0,
-        };
+        Ok((umem, descriptors_token))
+    }
 
-        set_xdp_umem_reg(umem.socket.as_fd(), umem_reg)?;
+    /// Returns how the chunks of the UMEM are laid out.
+    pub fn chunk_mode(&self) -> ChunkMode {
+        self.memory.chunk_mode()
+    }
 
-        Ok((umem, DescriptorsToken(PhantomData)))
+    /// Returns the pages actually backing the memory of the UMEM, `None` for memory provided through
+    /// [`UmemBuilder::memory_region`].
+    pub fn memory_backing(&self) -> Option<MemoryBacking> {
+        self.memory.backing()
     }
 
+    /// Returns a descriptor for each chunk of the UMEM.
+    ///
+    /// # Panics
+    ///
+    /// If `token` was created with another UMEM.
     pub fn descriptors(
         &'_ self,
         token: DescriptorsToken<Marker>,
     ) -> Vec<FillCompFrameDescriptor<'_, Marker, CHUNK_SIZE>> {
-        // Avoids having to prepend `_` to the variable, which would communicate it's not being used.
-        let _ = token;
-
-        (0..self.number_of_chunks)
-            .map(|chunk_index| {
-                let addr = (chunk_index * CHUNK_SIZE) as u64;
-                FillCompFrameDescriptor::from_ring_repr(addr, &self.memory)
-            })
-            .rev()
-            .collect()
-    }
-
-    pub(crate) fn bind_socket(
-        &self,
-        socket: Arc<OwnedFd>,
-        net_device_id: DeviceId,
-        queue_id: QueueId,
-    ) -> rustix::io::Result<()> {
-        if !self.initial_rings_given_out.swap(true, Ordering::AcqRel) {
-            // The initial socket.
-            let sockaddr_xdp = SocketAddrXdp::new(
-                // TODO: Make this configurable.
-                SocketAddrXdpFlags::XDP_USE_NEED_WAKEUP,
-                net_device_id.0,
-                queue_id.0,
-            );
-            bind(socket.as_fd(), &sockaddr_xdp)
-        } else {
-            // Follow-up socket.
-            let sockaddr_xdp = SocketAddrXdpWithSharedUmem {
-                addr: SocketAddrXdp::new(
-                    SocketAddrXdpFlags::XDP_SHARED_UMEM,
-                    net_device_id.0,
-                    queue_id.0,
-                ),
-                shared_umem_fd: self.socket.as_fd(),
-            };
-            bind(socket.as_fd(), &sockaddr_xdp)
-        }
+        token.redeem(&self.marker_guard);
+        self.memory.descriptors()
     }
 
-    pub(crate) fn xsk_map_socket(&self) -> Arc<OwnedFd> {
-        if self.initial_rings_given_out.load(Ordering::Acquire) {
-            let socket = socket_with(
-                AddressFamily::XDP,
-                SocketType::RAW,
-                SocketFlags::CLOEXEC,
-                None,
-            )
-            .unwrap();
-            Arc::new(socket)
-        } else {
-            self.socket.clone()
-        }
+    pub(crate) fn socket(&self) -> &UmemSocket {
+        &self.socket
     }
 
     pub(crate) fn memory(&self) -> &UmemMemory {
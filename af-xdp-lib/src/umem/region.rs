@@ -0,0 +1,88 @@
+use crate::error::Error;
+use crate::mmap::non_null_mapping;
+use rustix::mm::{MapFlags, ProtFlags, mmap, munmap};
+use std::fmt::Debug;
+use std::os::fd::AsFd;
+use std::ptr::NonNull;
+
+/// Memory owned by the caller that is registered as UMEM instead of memory allocated by the [`Umem`].
+///
+/// The region is moved into the [`Umem`] and dropped after the socket the UMEM is registered with was closed.
+///
+/// # Safety
+///
+/// Implementors must guarantee that:
+/// - [`as_ptr`](UmemRegion::as_ptr) returns a page-aligned pointer that is valid for reads and writes of
+///   [`len`](UmemRegion::len) bytes.
+/// - Both methods always return the same values.
+/// - The memory stays valid and isn't moved until the region is dropped.
+/// - The memory isn't accessed through Rust references while the region is registered. Other processes sharing the
+///   memory must only access chunks handed over to them, just like the kernel.
+///
+/// [`Umem`]: crate::umem::Umem
+pub unsafe trait UmemRegion: Debug + Send + Sync {
+    fn as_ptr(&self) -> NonNull<u8>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Shared, writable mapping of a file descriptor, e.g. a memfd, a shared memory object or a regular file.
+///
+/// The mapping is page-aligned and lives until the region is dropped. Mapping the same file descriptor in another
+/// process gives that process access to the frames without copying.
+#[derive(Debug)]
+pub struct MmapRegion {
+    memory: NonNull<u8>,
+    len: usize,
+}
+
+unsafe impl Send for MmapRegion {}
+unsafe impl Sync for MmapRegion {}
+
+impl MmapRegion {
+    /// Maps `len` bytes of `fd` starting at `offset`, which has to be a multiple of the page size.
+    ///
+    /// # Safety
+    ///
+    /// Until the region is dropped:
+    /// - The file must not be truncated below the mapped range, accesses to the missing pages raise `SIGBUS`.
+    /// - The mapped range must not be accessed through other mappings of the file in this process, the UMEM hands
+    ///   out mutable references into it. Other processes must only access chunks handed over to them, just like the
+    ///   kernel.
+    pub unsafe fn new(fd: impl AsFd, offset: u64, len: usize) -> Result<Self, Error> {
+        let memory = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                len,
+                ProtFlags::READ | ProtFlags::WRITE,
+                MapFlags::SHARED,
+                fd.as_fd(),
+                offset,
+            )
+        }?;
+
+        let memory = non_null_mapping(memory, len)?.cast();
+
+        Ok(Self { memory, len })
+    }
+}
+
+unsafe impl UmemRegion for MmapRegion {
+    fn as_ptr(&self) -> NonNull<u8> {
+        self.memory
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Drop for MmapRegion {
+    fn drop(&mut self) {
+        unsafe { munmap(self.memory.as_ptr().cast(), self.len) }.unwrap()
+    }
+}
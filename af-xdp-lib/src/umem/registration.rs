@@ -0,0 +1,351 @@
+use crate::descriptor::tx_metadata::{TxMetadata, XDP_UMEM_TX_METADATA_LEN};
+use crate::error::Error;
+use crate::ring::{RingKind, set_ring_size};
+use crate::socket::FillComp;
+use crate::umem::memory::UmemMemory;
+use crate::umem::region::UmemRegion;
+use crate::umem::{ChunkMode, DeviceId, MemoryBacking, QueueId};
+use rustix::io::Errno;
+use rustix::net::sockopt::{set_xdp_umem_reg, xdp_options};
+use rustix::net::xdp::{
+    SocketAddrXdp, SocketAddrXdpFlags, SocketAddrXdpWithSharedUmem, XdpOptionsFlags, XdpUmemReg,
+    XdpUmemRegFlags,
+};
+use rustix::net::{AddressFamily, SocketFlags, SocketType, bind, socket_with};
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::info;
+
+/// Options of a UMEM that don't depend on the chunk size, shared by the builders of the const and the dynamically
+/// sized UMEM.
+#[derive(Default)]
+pub(crate) struct UmemConfig {
+    pub(crate) headroom: u32,
+    pub(crate) chunk_mode: ChunkMode,
+    pub(crate) memory_backing: MemoryBacking,
+    pub(crate) memory_region: Option<Box<dyn UmemRegion>>,
+    pub(crate) tx_metadata: bool,
+}
+
+impl UmemConfig {
+    pub(crate) fn debug(&self, f: &mut Formatter<'_>, name: &str) -> std::fmt::Result {
+        f.debug_struct(name)
+            .field("headroom", &self.headroom)
+            .field("chunk_mode", &self.chunk_mode)
+            .field("memory_backing", &self.memory_backing)
+            .field("memory_region", &self.memory_region)
+            .field("tx_metadata", &self.tx_metadata)
+            .finish()
+    }
+
+    /// Allocates the memory for `number_of_chunks` chunks and registers it as UMEM.
+    pub(crate) fn register(
+        self,
+        chunk_size: usize,
+        number_of_chunks: usize,
+    ) -> Result<(UmemSocket, UmemMemory), Error> {
+        let memory = match self.memory_region {
+            Some(memory_region) => UmemMemory::from_region(
+                memory_region,
+                number_of_chunks,
+                chunk_size,
+                self.chunk_mode,
+            )?,
+            None => {
+                info!("Allocate memory.");
+                UmemMemory::new(
+                    number_of_chunks,
+                    chunk_size,
+                    self.chunk_mode,
+                    self.memory_backing,
+                )
+            }
+        };
+
+        let socket = UmemSocket {
+            socket: Arc::new(socket_with(
+                AddressFamily::XDP,
+                SocketType::RAW,
+                SocketFlags::CLOEXEC,
+                None,
+            )?),
+            initial_ring_sizes: Mutex::default(),
+            bindings: Arc::default(),
+        };
+
+        info!("Registering UMEM.");
+
+        let (tx_metadata_flags, tx_metadata_len) = if self.tx_metadata {
+            (XDP_UMEM_TX_METADATA_LEN, size_of::<TxMetadata>() as u32)
+        } else {
+            (XdpUmemRegFlags::empty(), 0)
+        };
+        let umem_reg = XdpUmemReg {
+            addr: memory.memory().as_ptr() as u64,
+            len: memory.allocation_length() as u64,
+            chunk_size: chunk_size as u32,
+            headroom: self.headroom,
+            flags: self.chunk_mode.umem_reg_flags() | tx_metadata_flags,
+            tx_metadata_len,
+        };
+
+        set_xdp_umem_reg(socket.socket.as_fd(), umem_reg)?;
+
+        Ok((socket, memory))
+    }
+}
+
+/// The socket a UMEM is registered with.
+///
+/// Has to be dropped before the memory of the UMEM.
+pub(crate) struct UmemSocket {
+    socket: Arc<OwnedFd>,
+    // Ring sizes already set on `socket`, the kernel allows setting them only once per socket.
+    initial_ring_sizes: Mutex<HashMap<RingKind, u32>>,
+    // Shared with the `QueueBinding` of each ring set, which releases its queue on drop.
+    bindings: Arc<Mutex<Bindings>>,
+}
+
+/// The sockets of a UMEM bound to devices and queues.
+#[derive(Default)]
+struct Bindings {
+    initial_rings_given_out: bool,
+    /// The flags the UMEM socket was bound with, which apply to all sockets sharing the UMEM.
+    umem_socket_flags: Option<SocketAddrXdpFlags>,
+    queues: HashMap<(DeviceId, QueueId), BoundQueue>,
+}
+
+/// The sockets of a UMEM bound to a device and queue, all of them share one fill and completion ring.
+struct BoundQueue {
+    /// The socket with the fill and completion ring, sockets sharing them are bound with it.
+    fill_comp_socket: Arc<OwnedFd>,
+    /// Whether `fill_comp_socket` is the UMEM socket, which stays bound until the UMEM is dropped.
+    umem_socket: bool,
+    /// Whether the ring set with the fill and completion ring is still alive.
+    fill_comp_rings: bool,
+    /// The number of ring sets bound to the device and queue, including the one with the fill and completion ring.
+    ring_sets: usize,
+}
+
+impl UmemSocket {
+    pub(crate) fn initial_rings_given_out(&self) -> bool {
+        self.bindings.lock().unwrap().initial_rings_given_out
+    }
+
+    /// Locks the bound sockets of the UMEM until a socket is bound with the returned lock or it's dropped.
+    ///
+    /// Choosing the socket, checking its fill and completion ring and binding it happen under the same lock, so
+    /// concurrently set up sockets can't both get the UMEM socket or a fill and completion ring for the same queue.
+    pub(crate) fn lock_bindings(&self) -> BindLock<'_> {
+        BindLock {
+            umem: self,
+            bindings: self.bindings.lock().unwrap(),
+        }
+    }
+
+    /// Returns whether `socket` is the socket the UMEM was registered with.
+    pub(crate) fn is_umem_socket(&self, socket: &Arc<OwnedFd>) -> bool {
+        Arc::ptr_eq(socket, &self.socket)
+    }
+
+    /// Sets the size of a ring of `socket`.
+    ///
+    /// Sizes set on the UMEM socket are remembered, so setting up its rings can be retried after a later step
+    /// failed. This mirrors `rx_ring_setup_done`/`tx_ring_setup_done` in libxdp.
+    pub(crate) fn set_ring_size(
+        &self,
+        socket: &Arc<OwnedFd>,
+        ring: RingKind,
+        size: u32,
+    ) -> Result<(), Error> {
+        if !self.is_umem_socket(socket) {
+            return set_ring_size(socket.as_fd(), ring, size);
+        }
+
+        let mut initial_ring_sizes = self.initial_ring_sizes.lock().unwrap();
+        match initial_ring_sizes.get(&ring) {
+            Some(&set_size) if set_size == size => Ok(()),
+            // The kernel rejects setting the size a second time with EINVAL as well.
+            Some(_) => Err(Error::RingSize {
+                ring,
+                errno: Errno::INVAL,
+            }),
+            None => {
+                set_ring_size(socket.as_fd(), ring, size)?;
+                initial_ring_sizes.insert(ring, size);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The locked bound sockets of a UMEM, see [`UmemSocket::lock_bindings`].
+pub(crate) struct BindLock<'umem> {
+    umem: &'umem UmemSocket,
+    bindings: MutexGuard<'umem, Bindings>,
+}
+
+impl BindLock<'_> {
+    /// Returns the UMEM socket if its rings weren't given out yet, a new socket otherwise.
+    pub(crate) fn socket(&self) -> Result<Arc<OwnedFd>, Error> {
+        if self.bindings.initial_rings_given_out {
+            let socket = socket_with(
+                AddressFamily::XDP,
+                SocketType::RAW,
+                SocketFlags::CLOEXEC,
+                None,
+            )?;
+            Ok(Arc::new(socket))
+        } else {
+            Ok(self.umem.socket.clone())
+        }
+    }
+
+    /// Returns the flags the UMEM socket was bound with, `None` if it isn't bound yet.
+    ///
+    /// They contain `XDP_COPY` or `XDP_ZEROCOPY` unless the kernel doesn't report the mode it chose.
+    pub(crate) fn umem_socket_flags(&self) -> Option<SocketAddrXdpFlags> {
+        self.bindings.umem_socket_flags
+    }
+
+    /// Returns whether a new socket bound to the device and queue has its own fill and completion ring.
+    ///
+    /// Fails up front instead of binding a socket the kernel rejects with EINVAL or whose fill ring can't be used.
+    pub(crate) fn own_fill_comp(
+        &self,
+        net_device_id: DeviceId,
+        queue_id: QueueId,
+        fill_comp: FillComp,
+    ) -> Result<bool, Error> {
+        match (
+            self.bindings.queues.get(&(net_device_id, queue_id)),
+            fill_comp,
+        ) {
+            (None, FillComp::Auto | FillComp::Own) => Ok(true),
+            (None, FillComp::Shared) => Err(Error::FillCompRingsRequired {
+                device_id: net_device_id,
+                queue_id,
+            }),
+            (Some(queue), FillComp::Auto | FillComp::Shared) if queue.fill_comp_rings => Ok(false),
+            // Either another ring set has the fill and completion ring, or it was dropped while other sockets are
+            // still bound to the queue. The kernel keeps the rings until all of them are closed, but they can't be
+            // mapped again.
+            (Some(_), _) => Err(Error::FillCompRingsInUse {
+                device_id: net_device_id,
+                queue_id,
+            }),
+        }
+    }
+
+    /// Binds `socket` to the device and queue and releases the lock.
+    ///
+    /// `own_fill_comp` has to be the result of [`own_fill_comp`](Self::own_fill_comp). The returned binding has to
+    /// be dropped after the rings of the socket.
+    pub(crate) fn bind(
+        mut self,
+        socket: Arc<OwnedFd>,
+        net_device_id: DeviceId,
+        queue_id: QueueId,
+        flags: SocketAddrXdpFlags,
+        own_fill_comp: bool,
+    ) -> Result<QueueBinding, Error> {
+        let queue = (net_device_id, queue_id);
+        let umem_socket = self.umem.is_umem_socket(&socket);
+        let result = if umem_socket {
+            // The initial socket.
+            let sockaddr_xdp = SocketAddrXdp::new(flags, net_device_id.0, queue_id.0);
+            bind(socket.as_fd(), &sockaddr_xdp)
+        } else {
+            // Follow-up socket, the kernel rejects any flags besides `XDP_SHARED_UMEM`.
+            //
+            // The kernel shares the fill and completion ring of the socket passed here if it's bound to the same
+            // device and queue, and sets up the ones of the new socket otherwise.
+            let shared_umem_socket = match self.bindings.queues.get(&queue) {
+                Some(bound_queue) if !own_fill_comp => &bound_queue.fill_comp_socket,
+                _ => &self.umem.socket,
+            };
+            let sockaddr_xdp = SocketAddrXdpWithSharedUmem {
+                addr: SocketAddrXdp::new(
+                    SocketAddrXdpFlags::XDP_SHARED_UMEM,
+                    net_device_id.0,
+                    queue_id.0,
+                ),
+                shared_umem_fd: shared_umem_socket.as_fd(),
+            };
+            bind(socket.as_fd(), &sockaddr_xdp)
+        };
+        result.map_err(|errno| Error::Bind {
+            device_id: net_device_id,
+            queue_id,
+            errno,
+        })?;
+
+        // Only mark the initial rings as given out once binding succeeded, so a failed setup can be retried.
+        if umem_socket {
+            self.bindings.initial_rings_given_out = true;
+            self.bindings.umem_socket_flags = Some(bound_flags(socket.as_fd(), flags));
+        }
+        if own_fill_comp {
+            self.bindings.queues.insert(
+                queue,
+                BoundQueue {
+                    fill_comp_socket: socket,
+                    umem_socket,
+                    fill_comp_rings: true,
+                    ring_sets: 1,
+                },
+            );
+        } else if let Some(bound_queue) = self.bindings.queues.get_mut(&queue) {
+            bound_queue.ring_sets += 1;
+        }
+
+        Ok(QueueBinding {
+            bindings: self.umem.bindings.clone(),
+            queue,
+            fill_comp_rings: own_fill_comp,
+        })
+    }
+}
+
+/// Returns `flags` of a bound socket with the mode the kernel chose if neither copy nor zero-copy was requested.
+fn bound_flags(socket: BorrowedFd, flags: SocketAddrXdpFlags) -> SocketAddrXdpFlags {
+    if flags.intersects(SocketAddrXdpFlags::XDP_COPY | SocketAddrXdpFlags::XDP_ZEROCOPY) {
+        return flags;
+    }
+    match xdp_options(socket) {
+        Ok(options) if options.contains(XdpOptionsFlags::XDP_OPTIONS_ZEROCOPY) => {
+            flags | SocketAddrXdpFlags::XDP_ZEROCOPY
+        }
+        Ok(_) => flags | SocketAddrXdpFlags::XDP_COPY,
+        // `XDP_OPTIONS` is only available since Linux 5.3.
+        Err(_) => flags,
+    }
+}
+
+/// A ring set bound to a device and queue, released again on drop.
+///
+/// Dropping the ring set with the fill and completion ring releases them, so no further socket can share them. The
+/// queue is released once all ring sets bound to it are dropped, unless it's bound with the UMEM socket.
+pub(crate) struct QueueBinding {
+    bindings: Arc<Mutex<Bindings>>,
+    queue: (DeviceId, QueueId),
+    fill_comp_rings: bool,
+}
+
+impl Drop for QueueBinding {
+    fn drop(&mut self) {
+        let mut bindings = self.bindings.lock().unwrap();
+        let Some(bound_queue) = bindings.queues.get_mut(&self.queue) else {
+            return;
+        };
+        bound_queue.ring_sets -= 1;
+        if self.fill_comp_rings {
+            bound_queue.fill_comp_rings = false;
+        }
+        if bound_queue.ring_sets == 0 && !bound_queue.umem_socket {
+            bindings.queues.remove(&self.queue);
+        }
+    }
+}
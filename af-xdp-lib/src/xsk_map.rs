@@ -1,11 +1,17 @@
-use crate::ring::{CompletionRing, FillRing, RxRing, TxRing};
+use crate::error::{Error, SourceError};
+use crate::ring::driver::{FillCompDriver, FrameSource};
+use crate::ring::{CompletionRing, FillRing, RxRing, TxRing, busy_poll};
+use crate::socket::SocketBuilder;
+use crate::umem::registration::{BindLock, QueueBinding};
 use crate::umem::{DeviceId, QueueId, Umem};
 use aya::maps::MapData;
+use rustix::io::Errno;
+use rustix::net::xdp::SocketAddrXdpFlags;
 use std::borrow::BorrowMut;
 use std::fmt::{Debug, Display};
-use std::marker::PhantomData;
-use std::os::fd::{AsFd, AsRawFd};
-use std::sync::Mutex;
+use std::ops::{ControlFlow, Deref, DerefMut};
+use std::os::fd::{AsFd, AsRawFd, OwnedFd};
+use std::sync::{Arc, Mutex};
 use tracing::{error, info};
 
 // https://docs.kernel.org/bpf/map_xskmap.html
@@ -23,40 +29,174 @@ pub trait XskMap {
     fn max_entries(&self) -> u32;
 }
 
-#[derive(Debug)]
-pub struct SetElementError(String);
+/// Error reported by the underlying map implementation.
+pub type MapError = SourceError;
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum SetElementError {
+    /// The index is not smaller than the maximum number of entries of the map.
+    IndexOutOfRange {
+        index: u32,
+        max_entries: u32,
+        source: MapError,
+    },
+    /// The entry at the index is already occupied by another socket, the map has no room left at this index.
+    Occupied { index: u32, source: MapError },
+    /// Any other error of the map.
+    Other(MapError),
+}
 
 impl Display for SetElementError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+        match self {
+            SetElementError::IndexOutOfRange {
+                index, max_entries, ..
+            } => write!(
+                f,
+                "index {index} is out of range for a map with {max_entries} entries"
+            ),
+            SetElementError::Occupied { index, .. } => {
+                write!(f, "entry at index {index} is already occupied")
+            }
+            SetElementError::Other(_) => f.write_str("failed to set map entry"),
+        }
+    }
+}
+
+impl std::error::Error for SetElementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SetElementError::IndexOutOfRange { source, .. }
+            | SetElementError::Occupied { source, .. }
+            | SetElementError::Other(source) => Some(source.as_ref()),
+        }
     }
 }
-#[derive(Debug)]
-pub struct UpdateElementError(String);
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum UpdateElementError {
+    /// The index is not smaller than the maximum number of entries of the map.
+    IndexOutOfRange {
+        index: u32,
+        max_entries: u32,
+        source: MapError,
+    },
+    /// There is no socket at the index to replace.
+    Vacant { index: u32, source: MapError },
+    /// Any other error of the map.
+    Other(MapError),
+}
 
 impl Display for UpdateElementError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+        match self {
+            UpdateElementError::IndexOutOfRange {
+                index, max_entries, ..
+            } => write!(
+                f,
+                "index {index} is out of range for a map with {max_entries} entries"
+            ),
+            UpdateElementError::Vacant { index, .. } => {
+                write!(f, "entry at index {index} is vacant")
+            }
+            UpdateElementError::Other(_) => f.write_str("failed to update map entry"),
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct UnsetElementError(String);
+impl std::error::Error for UpdateElementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UpdateElementError::IndexOutOfRange { source, .. }
+            | UpdateElementError::Vacant { source, .. }
+            | UpdateElementError::Other(source) => Some(source.as_ref()),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum UnsetElementError {
+    /// The index is not smaller than the maximum number of entries of the map.
+    IndexOutOfRange {
+        index: u32,
+        max_entries: u32,
+        source: MapError,
+    },
+    /// Any other error of the map.
+    Other(MapError),
+}
 
 impl Display for UnsetElementError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+        match self {
+            UnsetElementError::IndexOutOfRange {
+                index, max_entries, ..
+            } => write!(
+                f,
+                "index {index} is out of range for a map with {max_entries} entries"
+            ),
+            UnsetElementError::Other(_) => f.write_str("failed to unset map entry"),
+        }
     }
 }
 
+impl std::error::Error for UnsetElementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnsetElementError::IndexOutOfRange { source, .. }
+            | UnsetElementError::Other(source) => Some(source.as_ref()),
+        }
+    }
+}
+
+/// Returns the index and maximum number of entries if `error` is an out of bounds error.
+fn aya_out_of_bounds(error: &aya::maps::MapError) -> Option<(u32, u32)> {
+    if let aya::maps::MapError::OutOfBounds { index, max_entries } = error {
+        Some((*index, *max_entries))
+    } else {
+        None
+    }
+}
+
+/// Returns whether `error` is a failed syscall with the given error number.
+fn aya_syscall_errno(error: &aya::maps::MapError, errno: Errno) -> bool {
+    matches!(
+        error,
+        aya::maps::MapError::SyscallError(syscall_error)
+            if syscall_error.io_error.raw_os_error() == Some(errno.raw_os_error())
+    )
+}
+
 impl<T> XskMap for aya::maps::XskMap<T>
 where
     T: BorrowMut<MapData>,
 {
     fn set_element(&mut self, socket: impl AsRawFd, index: u32) -> Result<(), SetElementError> {
         const BPF_FLAG_NO_EXIST: u64 = 1;
-        self.set(index, socket, BPF_FLAG_NO_EXIST)
-            .map_err(|error| SetElementError(error.to_string()))
+        let max_entries = self.len();
+        self.set(index, socket, BPF_FLAG_NO_EXIST).map_err(|error| {
+            if let Some((index, max_entries)) = aya_out_of_bounds(&error) {
+                SetElementError::IndexOutOfRange {
+                    index,
+                    max_entries,
+                    source: error.into(),
+                }
+            } else if aya_syscall_errno(&error, Errno::EXIST) {
+                SetElementError::Occupied {
+                    index,
+                    source: error.into(),
+                }
+            } else if aya_syscall_errno(&error, Errno::TOOBIG) {
+                // The kernel checks the index of array maps as well.
+                SetElementError::IndexOutOfRange {
+                    index,
+                    max_entries,
+                    source: error.into(),
+                }
+            } else {
+                SetElementError::Other(error.into())
+            }
+        })
     }
 
     fn update_element(
@@ -65,13 +205,36 @@ where
         index: u32,
     ) -> Result<(), UpdateElementError> {
         const BPF_FLAG_EXIST: u64 = 2;
-        self.set(index, socket, BPF_FLAG_EXIST)
-            .map_err(|error| UpdateElementError(error.to_string()))
+        self.set(index, socket, BPF_FLAG_EXIST).map_err(|error| {
+            if let Some((index, max_entries)) = aya_out_of_bounds(&error) {
+                UpdateElementError::IndexOutOfRange {
+                    index,
+                    max_entries,
+                    source: error.into(),
+                }
+            } else if aya_syscall_errno(&error, Errno::NOENT) {
+                UpdateElementError::Vacant {
+                    index,
+                    source: error.into(),
+                }
+            } else {
+                UpdateElementError::Other(error.into())
+            }
+        })
     }
 
     fn unset_element(&mut self, index: u32) -> Result<(), UnsetElementError> {
-        self.unset(index)
-            .map_err(|error| UnsetElementError(error.to_string()))
+        self.unset(index).map_err(|error| {
+            if let Some((index, max_entries)) = aya_out_of_bounds(&error) {
+                UnsetElementError::IndexOutOfRange {
+                    index,
+                    max_entries,
+                    source: error.into(),
+                }
+            } else {
+                UnsetElementError::Other(error.into())
+            }
+        })
     }
 
     fn max_entries(&self) -> u32 {
@@ -79,44 +242,48 @@ where
     }
 }
 
-pub(crate) struct XskMapEntry<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize>
+/// A socket registered at an index of an XSKMAP, the entry is removed again on drop.
+///
+/// Shares the map with its storage, so ring sets and their handles don't borrow the storage.
+pub(crate) struct XskMapEntry<XM>
 where
     XM: XskMap,
-    Marker: 'static,
 {
-    xsk_map: &'xsk XskMapStorage<'umem, XM, Marker, CHUNK_SIZE>,
-    xsk_lifetime: PhantomData<&'xsk ()>,
+    xsk_map: Arc<Mutex<XM>>,
     index: u32,
 }
 
-impl<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize>
-    XskMapEntry<'umem, 'xsk, XM, Marker, CHUNK_SIZE>
+impl<XM> XskMapEntry<XM>
 where
     XM: XskMap,
 {
     pub(crate) fn new(
-        xsk_map: &'xsk XskMapStorage<'umem, XM, Marker, CHUNK_SIZE>,
+        xsk_map: &Arc<Mutex<XM>>,
         index: u32,
         socket: impl AsRawFd,
-    ) -> Result<Self, SetElementError> {
+    ) -> Result<Self, Error> {
         info!("registering socket at index: {}", index);
-        xsk_map.register(socket, index)?;
+        xsk_map
+            .lock()
+            .unwrap()
+            .set_element(socket, index)
+            .map_err(|error| Error::XskMapRegistration {
+                index,
+                source: error,
+            })?;
         Ok(Self {
-            xsk_map,
-            xsk_lifetime: PhantomData,
+            xsk_map: xsk_map.clone(),
             index,
         })
     }
 }
 
-impl<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize> Drop
-    for XskMapEntry<'umem, 'xsk, XM, Marker, CHUNK_SIZE>
+impl<XM> Drop for XskMapEntry<XM>
 where
     XM: XskMap,
-    Marker: 'static,
 {
     fn drop(&mut self) {
-        if let Err(error) = self.xsk_map.deregister(self.index) {
+        if let Err(error) = self.xsk_map.lock().unwrap().unset_element(self.index) {
             error!(
                 "failed to deregister socket at index {}: {}",
                 self.index, error
@@ -128,185 +295,839 @@ where
 pub struct XskMapStorage<'umem, XM, Marker, const CHUNK_SIZE: usize>
 where
     XM: XskMap,
-    Marker: 'static,
 {
-    xsk_map: Mutex<XM>,
+    xsk_map: Arc<Mutex<XM>>,
     net_device_id: DeviceId,
     umem: &'umem Umem<Marker, CHUNK_SIZE>,
 }
 
-impl<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize> XskMapStorage<'umem, XM, Marker, CHUNK_SIZE>
+impl<'umem, XM, Marker, const CHUNK_SIZE: usize> XskMapStorage<'umem, XM, Marker, CHUNK_SIZE>
 where
     XM: XskMap,
 {
+    /// Creates the storage for the sockets of `umem` bound to queues of `net_device_id`.
+    ///
+    /// Storages for several devices can share a UMEM. The first socket bound to each device and queue gets a fill and
+    /// completion ring, later sockets bound to it share them.
     pub fn new(
         xsk_map: XM,
         net_device_id: DeviceId,
         umem: &'umem Umem<Marker, CHUNK_SIZE>,
     ) -> Self {
         Self {
-            xsk_map: Mutex::new(xsk_map),
+            xsk_map: Arc::new(Mutex::new(xsk_map)),
             net_device_id,
             umem,
         }
     }
 
-    pub(crate) fn register(&self, socket: impl AsRawFd, index: u32) -> Result<(), SetElementError> {
-        self.xsk_map.lock().unwrap().set_element(socket, index)
+    /// Returns the XSKMAP, fails if ring sets or ring handles still register sockets in it.
+    pub fn into_inner(self) -> Result<XM, Self> {
+        match Arc::try_unwrap(self.xsk_map) {
+            Ok(xsk_map) => Ok(xsk_map.into_inner().unwrap()),
+            Err(xsk_map) => Err(Self { xsk_map, ..self }),
+        }
     }
 
-    pub(crate) fn deregister(&self, index: u32) -> Result<(), UnsetElementError> {
-        self.xsk_map.lock().unwrap().unset_element(index)
+    /// Sets up the rings of a new socket bound to `queue_id` and registers it at `map_index` in the XSKMAP.
+    ///
+    /// # Panics
+    ///
+    /// Panics if setting up the rings fails, see [`Self::try_rings`] for a fallible version.
+    pub fn rings<const RING_SIZE: usize>(
+        &self,
+        queue_id: QueueId,
+        map_index: u32,
+    ) -> Rings<'umem, Marker, XM, CHUNK_SIZE, RING_SIZE> {
+        self.try_rings(queue_id, map_index)
+            .expect("failed to set up rings")
     }
 
-    pub fn into_inner(self) -> XM {
-        // If there is no reference to this XskMap, then there is also no other reference to the Arc.
-        self.xsk_map.into_inner().unwrap()
+    /// Sets up the rings of a new socket bound to `queue_id` and registers it at `map_index` in the XSKMAP.
+    ///
+    /// Everything set up before a failing step is released again before the error is returned.
+    pub fn try_rings<const RING_SIZE: usize>(
+        &self,
+        queue_id: QueueId,
+        map_index: u32,
+    ) -> Result<Rings<'umem, Marker, XM, CHUNK_SIZE, RING_SIZE>, Error> {
+        self.socket_builder(queue_id, map_index).build()
     }
 
-    pub fn rings<const RING_SIZE: usize>(
-        &'xsk self,
+    /// Returns a builder to configure the socket before setting up its rings.
+    pub fn socket_builder(
+        &self,
         queue_id: QueueId,
         map_index: u32,
-    ) -> Rings<'umem, 'xsk, Marker, XM, CHUNK_SIZE, RING_SIZE> {
-        info!("rings");
-
-        let socket = self.umem.xsk_map_socket();
-
-        let rx_ring = RxRing::new(self.umem.memory(), socket.clone()).unwrap();
-        let tx_ring = TxRing::new(self.umem.memory(), socket.clone()).unwrap();
-        let fill_ring = FillRing::new(self.umem.memory(), socket.clone()).unwrap();
-        let completion_ring = CompletionRing::new(self.umem.memory(), socket.clone()).unwrap();
-
-        if self
-            .umem
-            .bind_socket(socket.clone(), self.net_device_id, queue_id)
-            .is_ok()
-        {
-            let xsk_map_entry = XskMapEntry::new(self, map_index, socket.as_fd()).unwrap();
-            Rings::Four(FillCompRxTxRings {
-                _xsk_map_entry: xsk_map_entry,
-                fill_ring,
-                completion_ring,
-                rx_ring,
-                tx_ring,
-            })
-        } else {
-            let socket = self.umem.xsk_map_socket();
-
-            let rx_ring = RxRing::new(self.umem.memory(), socket.clone()).unwrap();
-            let tx_ring = TxRing::new(self.umem.memory(), socket.clone()).unwrap();
-
-            self.umem
-                .bind_socket(socket.clone(), self.net_device_id, queue_id)
-                .unwrap();
-
-            let xsk_map_entry = XskMapEntry::new(self, map_index, socket.as_fd()).unwrap();
-            Rings::Two(RxTxRings {
-                _xsk_map_entry: xsk_map_entry,
-                rx_ring,
-                tx_ring,
-            })
-        }
+    ) -> SocketBuilder<'umem, '_, XM, Marker, CHUNK_SIZE> {
+        SocketBuilder::new(self, queue_id, map_index)
+    }
+
+    pub(crate) fn umem(&self) -> &'umem Umem<Marker, CHUNK_SIZE> {
+        self.umem
+    }
+
+    pub(crate) fn net_device_id(&self) -> DeviceId {
+        self.net_device_id
+    }
+
+    // Rings, map entries and follow-up sockets created by the following functions release themselves on drop, so
+    // returning early from a failed step cleans up the steps before.
+    //
+    // The socket is registered in the XSKMAP before it's bound, because a registration can be undone while a bind
+    // can't. Packets redirected to the socket in between are dropped by the kernel.
+
+    pub(crate) fn fill_comp_rx_tx_rings<
+        const FILL_SIZE: usize,
+        const COMPLETION_SIZE: usize,
+        const RX_SIZE: usize,
+        const TX_SIZE: usize,
+    >(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        map_index: u32,
+        bind_flags: SocketAddrXdpFlags,
+    ) -> Result<
+        FillCompRxTxRings<
+            'umem,
+            Marker,
+            XM,
+            CHUNK_SIZE,
+            FILL_SIZE,
+            COMPLETION_SIZE,
+            RX_SIZE,
+            TX_SIZE,
+        >,
+        Error,
+    > {
+        let rx_ring = RxRing::new(self.umem, socket.clone())?;
+        let tx_ring = TxRing::new(self.umem, socket.clone())?;
+        let fill_ring = FillRing::new(self.umem, socket.clone())?;
+        let completion_ring = CompletionRing::new(self.umem, socket.clone())?;
+
+        let xsk_map_entry = XskMapEntry::new(&self.xsk_map, map_index, socket.as_fd())?;
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, true)?;
+
+        Ok(FillCompRxTxRings {
+            _xsk_map_entry: xsk_map_entry,
+            fill_ring,
+            completion_ring,
+            rx_ring,
+            tx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+
+    pub(crate) fn rx_tx_rings<const RX_SIZE: usize, const TX_SIZE: usize>(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        map_index: u32,
+        bind_flags: SocketAddrXdpFlags,
+    ) -> Result<RxTxRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE, TX_SIZE>, Error> {
+        let rx_ring = RxRing::new(self.umem, socket.clone())?;
+        let tx_ring = TxRing::new(self.umem, socket.clone())?;
+
+        let xsk_map_entry = XskMapEntry::new(&self.xsk_map, map_index, socket.as_fd())?;
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, false)?;
+
+        Ok(RxTxRings {
+            _xsk_map_entry: xsk_map_entry,
+            rx_ring,
+            tx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+
+    pub(crate) fn fill_comp_rx_rings<
+        const FILL_SIZE: usize,
+        const COMPLETION_SIZE: usize,
+        const RX_SIZE: usize,
+    >(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        map_index: u32,
+        bind_flags: SocketAddrXdpFlags,
+    ) -> Result<
+        FillCompRxRings<'umem, Marker, XM, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, RX_SIZE>,
+        Error,
+    > {
+        let rx_ring = RxRing::new(self.umem, socket.clone())?;
+        let fill_ring = FillRing::new(self.umem, socket.clone())?;
+        let completion_ring = CompletionRing::new(self.umem, socket.clone())?;
+
+        let xsk_map_entry = XskMapEntry::new(&self.xsk_map, map_index, socket.as_fd())?;
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, true)?;
+
+        Ok(FillCompRxRings {
+            _xsk_map_entry: xsk_map_entry,
+            fill_ring,
+            completion_ring,
+            rx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+
+    pub(crate) fn rx_rings<const RX_SIZE: usize>(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        map_index: u32,
+        bind_flags: SocketAddrXdpFlags,
+    ) -> Result<RxOnlyRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE>, Error> {
+        let rx_ring = RxRing::new(self.umem, socket.clone())?;
+
+        let xsk_map_entry = XskMapEntry::new(&self.xsk_map, map_index, socket.as_fd())?;
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, false)?;
+
+        Ok(RxOnlyRings {
+            _xsk_map_entry: xsk_map_entry,
+            rx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+
+    // Without an RX ring, the XDP program can't redirect to the socket, so it isn't registered in the XSKMAP.
+
+    pub(crate) fn fill_comp_tx_rings<
+        const FILL_SIZE: usize,
+        const COMPLETION_SIZE: usize,
+        const TX_SIZE: usize,
+    >(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        bind_flags: SocketAddrXdpFlags,
+    ) -> Result<
+        FillCompTxRings<'umem, Marker, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, TX_SIZE>,
+        Error,
+    > {
+        let tx_ring = TxRing::new(self.umem, socket.clone())?;
+        let fill_ring = FillRing::new(self.umem, socket.clone())?;
+        let completion_ring = CompletionRing::new(self.umem, socket.clone())?;
+
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, true)?;
+
+        Ok(FillCompTxRings {
+            fill_ring,
+            completion_ring,
+            tx_ring,
+            _queue_binding: queue_binding,
+        })
+    }
+
+    pub(crate) fn tx_rings<const TX_SIZE: usize>(
+        &self,
+        socket: Arc<OwnedFd>,
+        bind_lock: BindLock<'umem>,
+        queue_id: QueueId,
+        bind_flags: SocketAddrXdpFlags,
+    ) -> Result<TxOnlyRings<'umem, Marker, CHUNK_SIZE, TX_SIZE>, Error> {
+        let tx_ring = TxRing::new(self.umem, socket.clone())?;
+
+        let queue_binding =
+            bind_lock.bind(socket, self.net_device_id, queue_id, bind_flags, false)?;
+
+        Ok(TxOnlyRings {
+            tx_ring,
+            _queue_binding: queue_binding,
+        })
     }
 }
 
-pub enum Rings<'umem, 'xsk, Marker, XM, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-where
+/// The rings of a socket, the sizes of the rings default to `FILL_SIZE`.
+pub enum Rings<
+    'umem,
+    Marker,
+    XM,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+    const RX_SIZE: usize = FILL_SIZE,
+    const TX_SIZE: usize = FILL_SIZE,
+> where
     XM: XskMap,
-    Marker: 'static,
 {
-    Two(RxTxRings<'umem, 'xsk, Marker, XM, CHUNK_SIZE, RING_SIZE>),
-    Four(FillCompRxTxRings<'umem, 'xsk, Marker, XM, CHUNK_SIZE, RING_SIZE>),
+    Two(RxTxRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE, TX_SIZE>),
+    Four(
+        FillCompRxTxRings<
+            'umem,
+            Marker,
+            XM,
+            CHUNK_SIZE,
+            FILL_SIZE,
+            COMPLETION_SIZE,
+            RX_SIZE,
+            TX_SIZE,
+        >,
+    ),
+    /// An RX-only socket sharing the fill ring of another socket, see [`Direction::Rx`](crate::socket::Direction::Rx).
+    Rx(RxOnlyRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE>),
+    /// An RX-only socket with its own fill and completion ring.
+    FillCompRx(FillCompRxRings<'umem, Marker, XM, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, RX_SIZE>),
+    /// A TX-only socket sharing the completion ring of another socket, see
+    /// [`Direction::Tx`](crate::socket::Direction::Tx).
+    Tx(TxOnlyRings<'umem, Marker, CHUNK_SIZE, TX_SIZE>),
+    /// A TX-only socket with its own fill and completion ring.
+    FillCompTx(FillCompTxRings<'umem, Marker, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, TX_SIZE>),
 }
 
-pub struct RxTxRings<'umem, 'xsk, Marker, XM, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-where
+/// Ring sets with the RX and TX ring of a socket, which can be driven with preferred busy polling.
+pub trait BusyPollRings {
+    /// Issues the syscalls that drive a socket with preferred busy polling, see
+    /// [`SocketBuilder::busy_poll`](crate::socket::SocketBuilder::busy_poll).
+    ///
+    /// Sends pending TX descriptors and processes RX regardless of the need wakeup flags. Has to be called once per
+    /// iteration of the processing loop, the kernel re-enables interrupts if the application doesn't call in time.
+    fn busy_poll(&mut self) -> Result<(), Error>;
+
+    /// Calls [`busy_poll`](Self::busy_poll) and `process` in a loop until `process` breaks.
+    fn busy_poll_loop<B>(
+        &mut self,
+        mut process: impl FnMut(&mut Self) -> ControlFlow<B>,
+    ) -> Result<B, Error>
+    where
+        Self: Sized,
+    {
+        loop {
+            self.busy_poll()?;
+            if let ControlFlow::Break(value) = process(self) {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+pub struct RxTxRings<
+    'umem,
+    Marker,
+    XM,
+    const CHUNK_SIZE: usize,
+    const RX_SIZE: usize,
+    const TX_SIZE: usize = RX_SIZE,
+> where
     XM: XskMap,
-    Marker: 'static,
 {
     // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
-    _xsk_map_entry: XskMapEntry<'umem, 'xsk, XM, Marker, CHUNK_SIZE>,
-    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    _xsk_map_entry: XskMapEntry<XM>,
+    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
 }
 
-impl<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-    RxTxRings<'umem, 'xsk, Marker, XM, CHUNK_SIZE, RING_SIZE>
+impl<'umem, XM, Marker, const CHUNK_SIZE: usize, const RX_SIZE: usize, const TX_SIZE: usize>
+    RxTxRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE, TX_SIZE>
 where
     XM: XskMap,
 {
-    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE> {
         &mut self.rx_ring
     }
 
-    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE> {
         &mut self.tx_ring
     }
 
     pub fn rings(
         &mut self,
     ) -> (
-        &mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-        &mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+        &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
     ) {
         (&mut self.rx_ring, &mut self.tx_ring)
     }
+
+    /// Splits the ring set into handles that can be moved to different threads.
+    ///
+    /// The socket stays registered in the XSKMAP until the last handle is dropped.
+    pub fn into_handles(
+        self,
+    ) -> (
+        RxHandle<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE>,
+        TxHandle<'umem, Marker, XM, CHUNK_SIZE, TX_SIZE>,
+    ) {
+        let xsk_map_entry = Arc::new(self._xsk_map_entry);
+        let queue_binding = Arc::new(self._queue_binding);
+        (
+            RingHandle::new(xsk_map_entry.clone(), self.rx_ring, queue_binding.clone()),
+            RingHandle::new(xsk_map_entry, self.tx_ring, queue_binding),
+        )
+    }
 }
 
+impl<'umem, XM, Marker, const CHUNK_SIZE: usize, const RX_SIZE: usize, const TX_SIZE: usize>
+    BusyPollRings for RxTxRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE, TX_SIZE>
+where
+    XM: XskMap,
+{
+    fn busy_poll(&mut self) -> Result<(), Error> {
+        busy_poll(&self.rx_ring, &self.tx_ring)
+    }
+}
+
+/// The four rings of a socket, the sizes of the rings default to `FILL_SIZE`.
 pub struct FillCompRxTxRings<
     'umem,
-    'xsk,
     Marker,
     XM,
     const CHUNK_SIZE: usize,
-    const RING_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+    const RX_SIZE: usize = FILL_SIZE,
+    const TX_SIZE: usize = FILL_SIZE,
 > where
     XM: XskMap,
-    Marker: 'static,
 {
     // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
-    _xsk_map_entry: XskMapEntry<'umem, 'xsk, XM, Marker, CHUNK_SIZE>,
-    fill_ring: FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-    completion_ring: CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    _xsk_map_entry: XskMapEntry<XM>,
+    fill_ring: FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+    completion_ring: CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
 }
 
-impl<'umem, 'xsk, XM, Marker, const CHUNK_SIZE: usize, const RING_SIZE: usize>
-    FillCompRxTxRings<'umem, 'xsk, Marker, XM, CHUNK_SIZE, RING_SIZE>
+impl<
+    'umem,
+    XM,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+    const RX_SIZE: usize,
+    const TX_SIZE: usize,
+> FillCompRxTxRings<'umem, Marker, XM, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, RX_SIZE, TX_SIZE>
 where
     XM: XskMap,
 {
-    pub fn fill_ring(&mut self) -> &mut FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+    pub fn fill_ring(&mut self) -> &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE> {
         &mut self.fill_ring
     }
 
-    pub fn completion_ring(&mut self) -> &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+    pub fn completion_ring(
+        &mut self,
+    ) -> &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE> {
         &mut self.completion_ring
     }
 
-    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE> {
         &mut self.rx_ring
     }
 
-    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE> {
+    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE> {
         &mut self.tx_ring
     }
 
     pub fn rings(
         &mut self,
     ) -> (
-        &mut FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-        &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-        &mut RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
-        &mut TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+        &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+        &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+        &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+        &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    ) {
+        (
+            &mut self.fill_ring,
+            &mut self.completion_ring,
+            &mut self.rx_ring,
+            &mut self.tx_ring,
+        )
+    }
+
+    /// Splits the ring set into handles that can be moved to different threads.
+    ///
+    /// The socket stays registered in the XSKMAP until the last handle is dropped.
+    pub fn into_handles(
+        self,
+    ) -> FillCompRxTxHandles<
+        'umem,
+        Marker,
+        XM,
+        CHUNK_SIZE,
+        FILL_SIZE,
+        COMPLETION_SIZE,
+        RX_SIZE,
+        TX_SIZE,
+    > {
+        let xsk_map_entry = Arc::new(self._xsk_map_entry);
+        let queue_binding = Arc::new(self._queue_binding);
+        (
+            RingHandle::new(xsk_map_entry.clone(), self.fill_ring, queue_binding.clone()),
+            RingHandle::new(
+                xsk_map_entry.clone(),
+                self.completion_ring,
+                queue_binding.clone(),
+            ),
+            RingHandle::new(xsk_map_entry.clone(), self.rx_ring, queue_binding.clone()),
+            RingHandle::new(xsk_map_entry, self.tx_ring, queue_binding),
+        )
+    }
+
+    /// Hands the fill and completion ring to a [`FillCompDriver`], which keeps the fill ring topped up to
+    /// `watermark` entries with frames from `source` and returns completed frames to it.
+    ///
+    /// # Panics
+    ///
+    /// If `watermark` exceeds `FILL_SIZE`.
+    pub fn into_driven<Source>(
+        self,
+        source: Source,
+        watermark: u32,
+    ) -> DrivenRings<
+        'umem,
+        Marker,
+        XM,
+        Source,
+        CHUNK_SIZE,
+        FILL_SIZE,
+        COMPLETION_SIZE,
+        RX_SIZE,
+        TX_SIZE,
+    >
+    where
+        Source: FrameSource<'umem, Marker, CHUNK_SIZE>,
+    {
+        DrivenRings {
+            _xsk_map_entry: self._xsk_map_entry,
+            driver: FillCompDriver::new(self.fill_ring, self.completion_ring, source, watermark),
+            rx_ring: self.rx_ring,
+            tx_ring: self.tx_ring,
+            _queue_binding: self._queue_binding,
+        }
+    }
+}
+
+impl<
+    'umem,
+    XM,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+    const RX_SIZE: usize,
+    const TX_SIZE: usize,
+> BusyPollRings
+    for FillCompRxTxRings<
+        'umem,
+        Marker,
+        XM,
+        CHUNK_SIZE,
+        FILL_SIZE,
+        COMPLETION_SIZE,
+        RX_SIZE,
+        TX_SIZE,
+    >
+where
+    XM: XskMap,
+{
+    fn busy_poll(&mut self) -> Result<(), Error> {
+        busy_poll(&self.rx_ring, &self.tx_ring)
+    }
+}
+
+pub struct RxOnlyRings<'umem, Marker, XM, const CHUNK_SIZE: usize, const RX_SIZE: usize>
+where
+    XM: XskMap,
+{
+    // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
+    _xsk_map_entry: XskMapEntry<XM>,
+    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<'umem, XM, Marker, const CHUNK_SIZE: usize, const RX_SIZE: usize>
+    RxOnlyRings<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE>
+where
+    XM: XskMap,
+{
+    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE> {
+        &mut self.rx_ring
+    }
+}
+
+/// The rings of an RX-only socket. The kernel requires a completion ring even though nothing is sent.
+pub struct FillCompRxRings<
+    'umem,
+    Marker,
+    XM,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+    const RX_SIZE: usize = FILL_SIZE,
+> where
+    XM: XskMap,
+{
+    // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
+    _xsk_map_entry: XskMapEntry<XM>,
+    fill_ring: FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+    completion_ring: CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<
+    'umem,
+    XM,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+    const RX_SIZE: usize,
+> FillCompRxRings<'umem, Marker, XM, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, RX_SIZE>
+where
+    XM: XskMap,
+{
+    pub fn fill_ring(&mut self) -> &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE> {
+        &mut self.fill_ring
+    }
+
+    pub fn completion_ring(
+        &mut self,
+    ) -> &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE> {
+        &mut self.completion_ring
+    }
+
+    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE> {
+        &mut self.rx_ring
+    }
+
+    pub fn rings(
+        &mut self,
+    ) -> (
+        &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+        &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+        &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
     ) {
         (
             &mut self.fill_ring,
             &mut self.completion_ring,
             &mut self.rx_ring,
+        )
+    }
+}
+
+pub struct TxOnlyRings<'umem, Marker, const CHUNK_SIZE: usize, const TX_SIZE: usize> {
+    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<'umem, Marker, const CHUNK_SIZE: usize, const TX_SIZE: usize>
+    TxOnlyRings<'umem, Marker, CHUNK_SIZE, TX_SIZE>
+{
+    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE> {
+        &mut self.tx_ring
+    }
+}
+
+/// The rings of a TX-only socket. The kernel requires a fill ring even though nothing is received, frames on it are
+/// used by RX sockets sharing it.
+pub struct FillCompTxRings<
+    'umem,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+    const TX_SIZE: usize = FILL_SIZE,
+> {
+    fill_ring: FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+    completion_ring: CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<
+    'umem,
+    Marker,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+    const TX_SIZE: usize,
+> FillCompTxRings<'umem, Marker, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, TX_SIZE>
+{
+    pub fn fill_ring(&mut self) -> &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE> {
+        &mut self.fill_ring
+    }
+
+    pub fn completion_ring(
+        &mut self,
+    ) -> &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE> {
+        &mut self.completion_ring
+    }
+
+    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE> {
+        &mut self.tx_ring
+    }
+
+    pub fn rings(
+        &mut self,
+    ) -> (
+        &mut FillRing<'umem, Marker, CHUNK_SIZE, FILL_SIZE>,
+        &mut CompletionRing<'umem, Marker, CHUNK_SIZE, COMPLETION_SIZE>,
+        &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    ) {
+        (
+            &mut self.fill_ring,
+            &mut self.completion_ring,
             &mut self.tx_ring,
         )
     }
 }
+
+/// A ring split off a ring set, e.g. by [`FillCompRxTxRings::into_handles`], dereferences to the ring.
+///
+/// All handles of a ring set share the socket's XSKMAP entry, it's removed when the last handle is dropped. Handles
+/// don't borrow the [`XskMapStorage`], they are `'static` if the UMEM is, e.g. a leaked one, and `Send` if the XSKMAP
+/// is. Descriptors point into the UMEM, so the UMEM itself has to outlive them.
+pub struct RingHandle<XM, Ring>
+where
+    XM: XskMap,
+{
+    // Dropped before the ring, like the map entry of the ring sets.
+    _xsk_map_entry: Arc<XskMapEntry<XM>>,
+    ring: Ring,
+    // Released after the ring, like the queue binding of the ring sets.
+    _queue_binding: Arc<QueueBinding>,
+}
+
+pub type FillHandle<'umem, Marker, XM, const CHUNK_SIZE: usize, const RING_SIZE: usize> =
+    RingHandle<XM, FillRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>>;
+pub type CompletionHandle<'umem, Marker, XM, const CHUNK_SIZE: usize, const RING_SIZE: usize> =
+    RingHandle<XM, CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>>;
+pub type RxHandle<'umem, Marker, XM, const CHUNK_SIZE: usize, const RING_SIZE: usize> =
+    RingHandle<XM, RxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>>;
+pub type TxHandle<'umem, Marker, XM, const CHUNK_SIZE: usize, const RING_SIZE: usize> =
+    RingHandle<XM, TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>>;
+/// The handles of [`FillCompRxTxRings::into_handles`], in the order of [`FillCompRxTxRings::rings`].
+pub type FillCompRxTxHandles<
+    'umem,
+    Marker,
+    XM,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+    const RX_SIZE: usize = FILL_SIZE,
+    const TX_SIZE: usize = FILL_SIZE,
+> = (
+    FillHandle<'umem, Marker, XM, CHUNK_SIZE, FILL_SIZE>,
+    CompletionHandle<'umem, Marker, XM, CHUNK_SIZE, COMPLETION_SIZE>,
+    RxHandle<'umem, Marker, XM, CHUNK_SIZE, RX_SIZE>,
+    TxHandle<'umem, Marker, XM, CHUNK_SIZE, TX_SIZE>,
+);
+
+impl<XM, Ring> RingHandle<XM, Ring>
+where
+    XM: XskMap,
+{
+    fn new(
+        xsk_map_entry: Arc<XskMapEntry<XM>>,
+        ring: Ring,
+        queue_binding: Arc<QueueBinding>,
+    ) -> Self {
+        Self {
+            _xsk_map_entry: xsk_map_entry,
+            ring,
+            _queue_binding: queue_binding,
+        }
+    }
+}
+
+impl<XM, Ring> Deref for RingHandle<XM, Ring>
+where
+    XM: XskMap,
+{
+    type Target = Ring;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ring
+    }
+}
+
+impl<XM, Ring> DerefMut for RingHandle<XM, Ring>
+where
+    XM: XskMap,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.ring
+    }
+}
+
+/// The RX and TX ring of a socket whose fill and completion ring are driven by a [`FillCompDriver`].
+pub struct DrivenRings<
+    'umem,
+    Marker,
+    XM,
+    Source,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize = FILL_SIZE,
+    const RX_SIZE: usize = FILL_SIZE,
+    const TX_SIZE: usize = FILL_SIZE,
+> where
+    XM: XskMap,
+{
+    // Drop the map entry before the rings to ensure the socket is removed from the XSKMAP before the memory is unmapped.
+    _xsk_map_entry: XskMapEntry<XM>,
+    driver: FillCompDriver<'umem, Marker, Source, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE>,
+    rx_ring: RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+    tx_ring: TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    // Released after the rings, which keep the socket bound to the queue.
+    _queue_binding: QueueBinding,
+}
+
+impl<
+    'umem,
+    XM,
+    Marker,
+    Source,
+    const CHUNK_SIZE: usize,
+    const FILL_SIZE: usize,
+    const COMPLETION_SIZE: usize,
+    const RX_SIZE: usize,
+    const TX_SIZE: usize,
+> DrivenRings<'umem, Marker, XM, Source, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE, RX_SIZE, TX_SIZE>
+where
+    XM: XskMap,
+    Source: FrameSource<'umem, Marker, CHUNK_SIZE>,
+{
+    pub fn driver(
+        &mut self,
+    ) -> &mut FillCompDriver<'umem, Marker, Source, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE> {
+        &mut self.driver
+    }
+
+    pub fn rx_ring(&mut self) -> &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE> {
+        &mut self.rx_ring
+    }
+
+    pub fn tx_ring(&mut self) -> &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE> {
+        &mut self.tx_ring
+    }
+
+    pub fn rings(
+        &mut self,
+    ) -> (
+        &mut FillCompDriver<'umem, Marker, Source, CHUNK_SIZE, FILL_SIZE, COMPLETION_SIZE>,
+        &mut RxRing<'umem, Marker, CHUNK_SIZE, RX_SIZE>,
+        &mut TxRing<'umem, Marker, CHUNK_SIZE, TX_SIZE>,
+    ) {
+        (&mut self.driver, &mut self.rx_ring, &mut self.tx_ring)
+    }
+}
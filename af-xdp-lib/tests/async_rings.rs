@@ -0,0 +1,94 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::ring::async_ring::{AsyncCompletionRing, AsyncRxRing, AsyncTxRing};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::time::timeout;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+const WAIT: Duration = Duration::from_millis(100);
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn async_rings() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("async", 9)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    let (fill_ring, completion_ring, rx_ring, tx_ring) = rings.rings();
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        fill_ring.push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    // The test runs on a worker of the multi-threaded runtime, which keeps driving the reactor meanwhile.
+    tokio::task::block_in_place(|| {
+        Handle::current().block_on(async {
+            let mut rx_ring = AsyncRxRing::new(rx_ring).unwrap();
+            let mut tx_ring = AsyncTxRing::new(tx_ring).unwrap();
+            let mut completion_ring = AsyncCompletionRing::new(completion_ring).unwrap();
+
+            // Nothing was sent yet.
+            assert!(timeout(WAIT, rx_ring.readable()).await.is_err());
+            assert!(timeout(WAIT, completion_ring.readable()).await.is_err());
+
+            let mut received = Vec::new();
+            for _ in 0..10 {
+                veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello async".to_vec());
+                if let Ok(popped) =
+                    timeout(WAIT, rx_ring.recv_batch(RING_SIZE as u32, &mut received)).await
+                {
+                    assert!(popped.unwrap() > 0);
+                    break;
+                }
+            }
+            assert!(!received.is_empty(), "no frame received");
+
+            // Send the received frames back out.
+            let sent = received.len();
+            assert_eq!(
+                tx_ring.send_batch(&mut received).await.unwrap(),
+                sent as u32
+            );
+            assert!(received.is_empty());
+
+            // The frames may complete one after the other.
+            let mut completed = Vec::new();
+            while completed.len() < sent {
+                let popped = timeout(
+                    WAIT,
+                    completion_ring.recv_batch(sent as u32, &mut completed),
+                )
+                .await
+                .expect("frames weren't sent");
+                assert!(popped.unwrap() > 0);
+            }
+            assert_eq!(completed.len(), sent);
+        })
+    });
+}
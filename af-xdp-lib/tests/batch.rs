@@ -0,0 +1,106 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::descriptor::{FillCompFrameDescriptor, RxTxFrameDescriptor};
+use af_xdp_lib::ring::{CompletionRing, TxRing};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 8;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const FRAME_LENGTH: u32 = 64;
+const FIRST_BATCH: usize = 5;
+const ROUNDS: usize = 4;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn batch() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("batch", 23)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    let (fill_ring, completion_ring, _, tx_ring) = rings.rings();
+
+    // Only as many descriptors as the ring has free entries are pushed, the rest stays in the vector.
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE - 3);
+    assert_eq!(
+        fill_ring.push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+    assert_eq!(fill_descriptors.len(), 3);
+    assert_eq!(fill_ring.push_batch(&mut fill_descriptors), 0);
+    assert_eq!(fill_descriptors.len(), 3);
+    descriptors.append(&mut fill_descriptors);
+
+    let mut tx_descriptors: Vec<_> = descriptors.drain(..).map(tx_frame).collect();
+
+    // A first batch smaller than the ring makes the later full batches wrap around the end of the ring.
+    let mut first_batch = tx_descriptors.split_off(tx_descriptors.len() - FIRST_BATCH);
+    assert_eq!(tx_ring.push_batch(&mut first_batch), FIRST_BATCH as u32);
+    assert!(first_batch.is_empty());
+    let completed = complete(tx_ring, completion_ring, FIRST_BATCH);
+    tx_descriptors.splice(0..0, completed.into_iter().map(tx_frame));
+
+    // The ring indices pass the ring size several times.
+    for _ in 0..ROUNDS {
+        let queued = tx_descriptors.len();
+        assert_eq!(tx_ring.push_batch(&mut tx_descriptors), RING_SIZE as u32);
+        assert_eq!(tx_descriptors.len(), queued - RING_SIZE);
+
+        let completed = complete(tx_ring, completion_ring, RING_SIZE);
+        tx_descriptors.splice(0..0, completed.into_iter().map(tx_frame));
+    }
+    // All descriptors but the ones in the fill ring are back.
+    assert_eq!(tx_descriptors.len(), CHUNK_NUM - RING_SIZE);
+}
+
+/// Waits until `count` frames were sent and pops their descriptors from the completion ring in partial batches.
+fn complete<'umem>(
+    tx_ring: &TxRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    completion_ring: &mut CompletionRing<'umem, Marker, CHUNK_SIZE, RING_SIZE>,
+    count: usize,
+) -> Vec<FillCompFrameDescriptor<'umem, Marker, CHUNK_SIZE>> {
+    for _ in 0..10 {
+        tx_ring.poke().unwrap();
+        if completion_ring.peek(count as u32).available() == count as u32 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    // The first pop takes fewer descriptors than completed, the second asks for more than are left.
+    let mut completed = Vec::new();
+    assert_eq!(completion_ring.pop_batch(3, &mut completed), 3);
+    assert_eq!(
+        completion_ring.pop_batch(RING_SIZE as u32, &mut completed),
+        count as u32 - 3
+    );
+    assert_eq!(completion_ring.pop_batch(1, &mut completed), 0);
+    completed
+}
+
+fn tx_frame(
+    descriptor: FillCompFrameDescriptor<'_, Marker, CHUNK_SIZE>,
+) -> RxTxFrameDescriptor<'_, Marker, CHUNK_SIZE> {
+    let mut descriptor = RxTxFrameDescriptor::from(descriptor);
+    descriptor.set_length(FRAME_LENGTH).unwrap();
+    descriptor
+}
@@ -0,0 +1,76 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::socket::BindMode;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+
+const RING_SIZE: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn bind_options() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("bind_opts", 28)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, _descriptors_token) = Umem::<Marker, 4096>::new(0, 1024).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+
+    let Ok(Rings::Four(_umem_rings)) = xsk_map
+        .socket_builder(QUEUE_ID, 0)
+        .bind_mode(BindMode::Copy)
+        .build::<RING_SIZE>()
+    else {
+        panic!("Failed to get rings");
+    };
+
+    // Sockets sharing the UMEM get the options of the UMEM socket, requesting others must not be ignored.
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QUEUE_ID, 1)
+            .bind_mode(BindMode::ZeroCopy)
+            .build::<RING_SIZE>(),
+        Err(Error::BindOptionsMismatch {
+            queue_id: QUEUE_ID,
+            bind_mode: BindMode::Copy,
+            need_wakeup: true,
+            multi_buffer: false,
+            ..
+        })
+    ));
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QUEUE_ID, 1)
+            .need_wakeup(false)
+            .build::<RING_SIZE>(),
+        Err(Error::BindOptionsMismatch { .. })
+    ));
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QUEUE_ID, 1)
+            .multi_buffer(true)
+            .build::<RING_SIZE>(),
+        Err(Error::BindOptionsMismatch { .. })
+    ));
+
+    // Matching options and the automatic mode are accepted.
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QUEUE_ID, 1)
+            .bind_mode(BindMode::Copy)
+            .build::<RING_SIZE>(),
+        Ok(Rings::Two(_))
+    ));
+    assert!(matches!(
+        xsk_map.socket_builder(QUEUE_ID, 2).build::<RING_SIZE>(),
+        Ok(Rings::Two(_))
+    ));
+}
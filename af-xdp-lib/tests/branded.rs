@@ -0,0 +1,81 @@
+mod utils;
+
+use crate::utils::setup::{setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::umem::{Brand, QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn branded() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("branded", 20)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let completed = Umem::<Brand, CHUNK_SIZE>::builder()
+        .with(CHUNK_NUM, |umem, descriptors_token| {
+            // Another branded UMEM can be alive at the same time.
+            let other_chunks = Umem::<Brand, CHUNK_SIZE>::builder()
+                .with(CHUNK_NUM, |other_umem, other_token| {
+                    other_umem.descriptors(other_token).len()
+                })
+                .unwrap();
+            assert_eq!(other_chunks, CHUNK_NUM);
+
+            let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+            let mut descriptors = umem.descriptors(descriptors_token);
+
+            let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+                panic!("Failed to get rings");
+            };
+
+            let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+            assert_eq!(
+                rings.fill_ring().push_batch(&mut fill_descriptors),
+                RING_SIZE as u32
+            );
+
+            let mut received = None;
+            for _ in 0..10 {
+                veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello branded".to_vec());
+                thread::sleep(Duration::from_millis(100));
+                rings.fill_ring().poke().unwrap();
+                received = rings.rx_ring().pop();
+                if received.is_some() {
+                    break;
+                }
+            }
+            let received = received.expect("no frame received");
+
+            rings.tx_ring().push(received).unwrap();
+            let mut completed = Vec::new();
+            for _ in 0..10 {
+                rings.tx_ring().poke().unwrap();
+                thread::sleep(Duration::from_millis(100));
+                rings
+                    .completion_ring()
+                    .pop_batch(RING_SIZE as u32, &mut completed);
+                if !completed.is_empty() {
+                    break;
+                }
+            }
+            completed.len()
+        })
+        .unwrap();
+    assert_eq!(completed, 1, "frame wasn't sent");
+}
@@ -0,0 +1,90 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::socket::BusyPoll;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{BusyPollRings, Rings, XskMapStorage};
+use aya::Ebpf;
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn busy_poll() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("busypoll", 11)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .busy_poll(BusyPoll::default())
+        .build::<RING_SIZE>()
+        .unwrap()
+    else {
+        panic!("Failed to get rings");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    // RX: the driving loop processes the received frame.
+    let start = Instant::now();
+    let mut last_send = None;
+    let received = rings
+        .busy_poll_loop(|rings| {
+            if let Some(frame) = rings.rx_ring().pop() {
+                return ControlFlow::Break(Some(frame));
+            }
+            if start.elapsed() > TIMEOUT {
+                return ControlFlow::Break(None);
+            }
+            if last_send.is_none_or(|sent: Instant| sent.elapsed() > TIMEOUT / 10) {
+                veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello busy poll".to_vec());
+                last_send = Some(Instant::now());
+            }
+            thread::sleep(Duration::from_millis(1));
+            ControlFlow::Continue(())
+        })
+        .unwrap()
+        .expect("no frame received");
+
+    // TX: the driving loop sends the frame back out without checking the need wakeup flag.
+    rings.tx_ring().push(received).unwrap();
+    let start = Instant::now();
+    let completed = rings
+        .busy_poll_loop(|rings| {
+            if let Some(frame) = rings.completion_ring().pop() {
+                return ControlFlow::Break(Some(frame));
+            }
+            if start.elapsed() > TIMEOUT {
+                return ControlFlow::Break(None);
+            }
+            thread::sleep(Duration::from_millis(1));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert!(completed.is_some(), "frame wasn't sent");
+}
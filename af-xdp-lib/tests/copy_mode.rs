@@ -0,0 +1,69 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::socket::BindMode;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const RING_SIZE: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn copy_mode() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("copy", 3)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, 4096>::new(0, 1024).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    // veth doesn't support zero-copy, requiring it must not silently fall back to copy mode.
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QUEUE_ID, QUEUE_ID.0)
+            .bind_mode(BindMode::ZeroCopy)
+            .build::<RING_SIZE>(),
+        Err(Error::ZeroCopyUnavailable { .. })
+    ));
+
+    let Rings::Four(mut rings) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .bind_mode(BindMode::Copy)
+        .build::<RING_SIZE>()
+        .unwrap()
+    else {
+        panic!("Failed to get rings");
+    };
+    assert!(!rings.rx_ring().is_zero_copy().unwrap());
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello copy mode".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        received = rings.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no frame received in copy mode");
+    assert!(received.length() > 0);
+}
@@ -0,0 +1,95 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::ring::driver::DriverStats;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+const WATERMARK: u32 = 16;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn driver() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("driver", 13)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    // Just enough frames to fill the ring to the watermark once.
+    let source = descriptors.split_off(descriptors.len() - WATERMARK as usize);
+    let mut rings = rings.into_driven(source, WATERMARK);
+
+    rings.driver().drive().unwrap();
+    assert_eq!(
+        rings.driver().stats(),
+        DriverStats {
+            filled: u64::from(WATERMARK),
+            ..DriverStats::default()
+        }
+    );
+    assert!(rings.driver().source().is_empty());
+
+    // Receive until the kernel consumed all frames of the fill ring.
+    let mut received = Vec::new();
+    for _ in 0..10 {
+        for _ in 0..WATERMARK {
+            veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello driver".to_vec());
+        }
+        thread::sleep(Duration::from_millis(100));
+        rings
+            .rx_ring()
+            .pop_batch(WATERMARK - received.len() as u32, &mut received);
+        if received.len() == WATERMARK as usize {
+            break;
+        }
+    }
+    assert_eq!(
+        received.len(),
+        WATERMARK as usize,
+        "frames weren't received"
+    );
+
+    // The received frames are missing on the fill ring, but the source is empty. The starvation is only counted once
+    // while the fill ring stays empty.
+    rings.driver().drive().unwrap();
+    rings.driver().drive().unwrap();
+    let stats = rings.driver().stats();
+    assert_eq!(stats.fill_ring_empty, 1);
+    assert_eq!(stats.source_empty, 2);
+    let received = received.pop().unwrap();
+
+    // Once the frame was sent, the driver recycles it into the fill ring.
+    rings.tx_ring().push(received).unwrap();
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        rings.driver().drive().unwrap();
+        if rings.driver().stats().completed == 1 {
+            break;
+        }
+    }
+    let stats = rings.driver().stats();
+    assert_eq!(stats.completed, 1, "frame wasn't sent");
+    assert!(stats.filled > u64::from(WATERMARK));
+}
@@ -0,0 +1,119 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::dynamic::umem::DynUmem;
+use af_xdp_lib::dynamic::xsk_map::{DynRings, DynXskMapStorage};
+use af_xdp_lib::error::Error;
+use af_xdp_lib::ring::RingKind;
+use af_xdp_lib::socket::{BindMode, FillComp};
+use af_xdp_lib::umem::QueueId;
+use aya::Ebpf;
+use std::hint::black_box;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_NUM: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn dynamic() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("dynamic", 15)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    // Sizes as they'd come from command line arguments.
+    let chunk_size: usize = black_box(2048);
+    let ring_size: u32 = black_box(32);
+
+    assert!(matches!(
+        DynUmem::<Marker>::new(3000, 0, CHUNK_NUM),
+        Err(Error::InvalidChunkSize { chunk_size: 3000 })
+    ));
+
+    let (umem, descriptors_token) = DynUmem::<Marker>::new(chunk_size, 0, CHUNK_NUM).unwrap();
+    assert_eq!(umem.chunk_size(), chunk_size);
+    let xsk_map = DynXskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    assert!(matches!(
+        xsk_map.try_rings(QUEUE_ID, QUEUE_ID.0, 48),
+        Err(Error::InvalidRingSize {
+            ring: RingKind::Rx,
+            size: 48
+        })
+    ));
+
+    let DynRings::Four(mut rings) = xsk_map.rings(QUEUE_ID, QUEUE_ID.0, ring_size) else {
+        panic!("Failed to get rings");
+    };
+    assert_eq!(rings.rx_ring().size(), ring_size);
+
+    // More descriptors than fit into the ring.
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - 2 * ring_size as usize);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        ring_size
+    );
+    assert_eq!(fill_descriptors.len(), ring_size as usize);
+    assert!(rings.fill_ring().is_full());
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello dynamic".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        rings.fill_ring().poke().unwrap();
+        received = rings.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no frame received");
+    assert_eq!(received.memory().len(), chunk_size);
+    let data = &received.memory()[received.data_offset()..][..received.length()];
+    assert!(data.ends_with(b"hello dynamic"));
+
+    rings.tx_ring().push(received).unwrap();
+    let mut completed = Vec::new();
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        rings.completion_ring().pop_batch(ring_size, &mut completed);
+        if !completed.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(completed.len(), 1, "frame wasn't sent");
+
+    // Further sockets of the queue share the fill and completion ring, there are no RX-only or TX-only ring sets.
+    let DynRings::Two(mut shared) = xsk_map.rings(QUEUE_ID, 1, ring_size) else {
+        panic!("Failed to get shared rings");
+    };
+    assert_eq!(shared.tx_ring().size(), ring_size);
+
+    // The builder options behave like the ones of the const generic builder.
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QueueId(1), 2)
+            .fill_comp(FillComp::Shared)
+            .build(ring_size),
+        Err(Error::FillCompRingsRequired {
+            queue_id: QueueId(1),
+            ..
+        })
+    ));
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QUEUE_ID, 2)
+            .bind_mode(BindMode::ZeroCopy)
+            .build(ring_size),
+        Err(Error::BindOptionsMismatch { .. })
+    ));
+}
@@ -0,0 +1,70 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{SetElementError, XskMapStorage};
+use af_xdp_test_common::SOCKS_MAP_SIZE;
+use aya::Ebpf;
+use aya::maps::MapError;
+use std::collections::HashSet;
+use std::error::Error as _;
+
+const RING_SIZE: usize = 64;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn errors() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("errors", 26)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, _descriptors_token) = Umem::<Marker, 4096>::new(0, 1024).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QueueId(0), SOCKS_MAP_SIZE),
+        Err(Error::XskMapRegistration {
+            source: SetElementError::IndexOutOfRange {
+                index,
+                max_entries,
+                ..
+            },
+            ..
+        }) if index == SOCKS_MAP_SIZE && max_entries == SOCKS_MAP_SIZE
+    ));
+
+    let _rings = xsk_map.try_rings::<RING_SIZE>(QueueId(0), 0).unwrap();
+    let Err(error) = xsk_map.try_rings::<RING_SIZE>(QueueId(0), 0) else {
+        panic!("socket registered at an occupied index");
+    };
+    assert!(matches!(
+        error,
+        Error::XskMapRegistration {
+            index: 0,
+            source: SetElementError::Occupied { index: 0, .. },
+        }
+    ));
+
+    // The cause is reachable through the source chain.
+    let map_error = error.source().unwrap();
+    assert!(map_error.is::<SetElementError>());
+    let aya_error = map_error.source().unwrap().downcast_ref::<MapError>();
+    assert!(matches!(
+        aya_error,
+        Some(MapError::SyscallError(error)) if error.io_error.raw_os_error() == Some(libc::EEXIST)
+    ));
+
+    // Clones are equal and hash the same, the same error from another attempt isn't the same instance.
+    let clone = error.clone();
+    assert_eq!(clone, error);
+    let Err(other) = xsk_map.try_rings::<RING_SIZE>(QueueId(0), 0) else {
+        panic!("socket registered at an occupied index");
+    };
+    assert_ne!(other, error);
+    let errors: HashSet<Error> = [error, clone, other].into_iter().collect();
+    assert_eq!(errors.len(), 2);
+}
@@ -0,0 +1,93 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair_with};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::socket::FillComp;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const THREADS: u32 = 3;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn fill_comp() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair_with("fill_comp", 27, 2, None)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, _descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+
+    // Sockets set up concurrently for the same queue get a single fill and completion ring.
+    let rings: Vec<_> = thread::scope(|scope| {
+        let threads: Vec<_> = (0..THREADS)
+            .map(|map_index| {
+                let xsk_map = &xsk_map;
+                scope.spawn(move || xsk_map.try_rings::<RING_SIZE>(QueueId(0), map_index))
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap().unwrap())
+            .collect()
+    });
+    let four = rings
+        .iter()
+        .filter(|rings| matches!(rings, Rings::Four(_)))
+        .count();
+    let two = rings
+        .iter()
+        .filter(|rings| matches!(rings, Rings::Two(_)))
+        .count();
+    assert_eq!((four, two), (1, THREADS as usize - 1));
+
+    // Queue 1 has no socket yet, there is no fill and completion ring to share.
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QueueId(1), THREADS)
+            .fill_comp(FillComp::Shared)
+            .build::<RING_SIZE>(),
+        Err(Error::FillCompRingsRequired {
+            queue_id: QueueId(1),
+            ..
+        })
+    ));
+
+    let Ok(Rings::Four(_queue_1)) = xsk_map
+        .socket_builder(QueueId(1), THREADS)
+        .fill_comp(FillComp::Own)
+        .build::<RING_SIZE>()
+    else {
+        panic!("Failed to get rings of queue 1");
+    };
+
+    // Queue 1 has a fill and completion ring now, a second one for it is rejected.
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QueueId(1), THREADS + 1)
+            .fill_comp(FillComp::Own)
+            .build::<RING_SIZE>(),
+        Err(Error::FillCompRingsInUse {
+            queue_id: QueueId(1),
+            ..
+        })
+    ));
+
+    assert!(matches!(
+        xsk_map
+            .socket_builder(QueueId(1), THREADS + 1)
+            .fill_comp(FillComp::Shared)
+            .build::<RING_SIZE>(),
+        Ok(Rings::Two(_))
+    ));
+}
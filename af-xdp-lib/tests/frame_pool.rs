@@ -0,0 +1,169 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::descriptor::RxTxFrameDescriptor;
+use af_xdp_lib::umem::frame_pool::{FramePool, FramePoolStats};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+const CACHE_CAPACITY: usize = 8;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn frame_pool() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("framepool", 12)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let pool = FramePool::new(umem.descriptors(descriptors_token));
+    assert_eq!(pool.stats().free, CHUNK_NUM);
+
+    // Threads allocate through their own caches, no frame is handed out twice.
+    let allocated: Vec<_> = thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut cache = pool.cache(CACHE_CAPACITY);
+                    let mut descriptors = Vec::new();
+                    while descriptors.len() < CHUNK_NUM / 4 {
+                        descriptors.extend(cache.alloc());
+                    }
+                    descriptors
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect()
+    });
+    let mut allocated: Vec<RxTxFrameDescriptor<_, CHUNK_SIZE>> =
+        allocated.into_iter().map(Into::into).collect();
+    let addrs: HashSet<_> = allocated
+        .iter()
+        .map(|descriptor| descriptor.memory().as_ptr())
+        .collect();
+    assert_eq!(addrs.len(), CHUNK_NUM);
+    assert_eq!(
+        pool.stats(),
+        FramePoolStats {
+            in_use: CHUNK_NUM,
+            ..FramePoolStats::default()
+        }
+    );
+    assert!(pool.alloc().is_none());
+
+    pool.free_bulk(&mut allocated);
+    assert_eq!(pool.stats().free, CHUNK_NUM);
+
+    // Requests larger than the cache take the remaining frames from the pool, the cache doesn't hold more than its
+    // capacity.
+    let mut cache = pool.cache(CACHE_CAPACITY);
+    let mut descriptors = Vec::new();
+    let wanted = CACHE_CAPACITY * 3;
+    assert_eq!(cache.alloc_bulk(wanted, &mut descriptors), wanted);
+    assert_eq!(descriptors.len(), wanted);
+    assert_eq!(pool.stats().in_use, wanted);
+    let mut uncached = Vec::new();
+    assert_eq!(
+        pool.alloc_bulk(CHUNK_NUM, &mut uncached),
+        CHUNK_NUM - wanted
+    );
+    pool.free_bulk(&mut uncached);
+    cache.free_bulk(&mut descriptors);
+    drop(cache);
+    assert_eq!(pool.stats().free, CHUNK_NUM);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    let mut cache = pool.cache(CACHE_CAPACITY);
+
+    // The fill ring takes at most `RING_SIZE` frames.
+    assert_eq!(cache.fill(rings.fill_ring(), CHUNK_NUM), RING_SIZE as u32);
+    assert_eq!(pool.stats().fill_rx, RING_SIZE);
+    assert_eq!(pool.stats().free, CHUNK_NUM - RING_SIZE);
+
+    let mut received = Vec::new();
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello frame pool".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        if cache.receive(rings.rx_ring(), 1, &mut received) > 0 {
+            break;
+        }
+    }
+    assert_eq!(received.len(), 1, "no frame received");
+    assert_eq!(pool.stats().fill_rx, RING_SIZE - 1);
+    assert_eq!(pool.stats().in_use, 1);
+
+    // Send the frame back out and recycle it once completed.
+    assert_eq!(cache.transmit(rings.tx_ring(), &mut received), 1);
+    assert_eq!(pool.stats().tx_completion, 1);
+    let mut completed = 0;
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        completed += cache.complete(rings.completion_ring(), 1);
+        if completed == 1 {
+            break;
+        }
+    }
+    assert_eq!(completed, 1, "frame wasn't sent");
+    assert_eq!(
+        pool.stats(),
+        FramePoolStats {
+            free: CHUNK_NUM - RING_SIZE + 1,
+            in_use: 0,
+            fill_rx: RING_SIZE - 1,
+            tx_completion: 0,
+        }
+    );
+
+    // A frame pushed to the fill ring directly counts as in use, receiving it through the cache keeps it counted once.
+    rings.fill_ring().push(pool.alloc().unwrap()).unwrap();
+    let mut received = Vec::new();
+    for _ in 0..10 {
+        for _ in 0..RING_SIZE {
+            veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello frame pool".to_vec());
+        }
+        thread::sleep(Duration::from_millis(100));
+        let missing = (RING_SIZE - received.len()) as u32;
+        cache.receive(rings.rx_ring(), missing, &mut received);
+        if received.len() == RING_SIZE {
+            break;
+        }
+    }
+    assert_eq!(received.len(), RING_SIZE, "frames weren't received");
+    assert_eq!(
+        pool.stats(),
+        FramePoolStats {
+            free: CHUNK_NUM - RING_SIZE,
+            in_use: RING_SIZE,
+            fill_rx: 0,
+            tx_completion: 0,
+        }
+    );
+
+    cache.free_bulk(&mut received);
+    assert_eq!(pool.stats().free, CHUNK_NUM);
+    assert_eq!(pool.stats().in_use, 0);
+}
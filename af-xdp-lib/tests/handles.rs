@@ -0,0 +1,96 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, SetElementError, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn handles() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("handles", 14)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    // Descriptors borrow the UMEM, handles moved to other threads need it to live forever.
+    let umem: &'static Umem<Marker, CHUNK_SIZE> = Box::leak(Box::new(umem));
+    let xsk_map = XskMapStorage::new(socks, device_id, umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    let (mut fill, mut completion, mut rx, mut tx) = rings.into_handles();
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(fill.push_batch(&mut fill_descriptors), RING_SIZE as u32);
+
+    let receiver = thread::spawn(move || {
+        for _ in 0..20 {
+            if let Some(descriptor) = rx.pop() {
+                return descriptor;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        panic!("no frame received");
+    });
+    while !receiver.is_finished() {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello handles".to_vec());
+        thread::sleep(Duration::from_millis(100));
+    }
+    let received = receiver.join().unwrap();
+
+    thread::spawn(move || {
+        tx.push(received).unwrap();
+        let mut completed = Vec::new();
+        for _ in 0..10 {
+            tx.poke().unwrap();
+            thread::sleep(Duration::from_millis(100));
+            completion.pop_batch(1, &mut completed);
+            if !completed.is_empty() {
+                return;
+            }
+        }
+        panic!("frame wasn't sent");
+    })
+    .join()
+    .unwrap();
+
+    // The RX, TX and completion handle were dropped by their threads, the fill handle keeps the socket registered.
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0),
+        Err(Error::XskMapRegistration {
+            index: 0,
+            source: SetElementError::Occupied { index: 0, .. },
+        })
+    ));
+
+    // Dropping the fill handle releases the XSKMAP and the fill and completion ring. The UMEM socket stays bound to
+    // the queue, so a new socket can't use them.
+    drop(fill);
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0),
+        Err(Error::FillCompRingsInUse {
+            queue_id: QUEUE_ID,
+            ..
+        })
+    ));
+    assert!(xsk_map.into_inner().is_ok());
+}
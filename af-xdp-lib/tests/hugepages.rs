@@ -0,0 +1,75 @@
+mod utils;
+
+use crate::utils::setup::{setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::umem::{MemoryBacking, QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::fs;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 1024;
+
+const RING_SIZE: usize = 64;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn hugepages() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("hugepages", 5)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    // Whether the hugepages are available depends on the host, the UMEM has to be usable either way.
+    pub struct Marker2M;
+    let (umem, _descriptors_token) = Umem::<Marker2M, CHUNK_SIZE>::builder()
+        .memory_backing(MemoryBacking::HugePages2M)
+        .build(CHUNK_NUM)
+        .unwrap();
+    assert_eq!(
+        umem.memory_backing(),
+        Some(expected_backing(
+            MemoryBacking::HugePages2M,
+            "hugepages-2048kB",
+            2
+        ))
+    );
+
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QueueId(0), 0),
+        Ok(Rings::Four(_))
+    ));
+
+    pub struct Marker1G;
+    let (umem, _descriptors_token) = Umem::<Marker1G, CHUNK_SIZE>::builder()
+        .memory_backing(MemoryBacking::HugePages1G)
+        .build(CHUNK_NUM)
+        .unwrap();
+    assert_eq!(
+        umem.memory_backing(),
+        Some(expected_backing(
+            MemoryBacking::HugePages1G,
+            "hugepages-1048576kB",
+            1
+        ))
+    );
+}
+
+fn expected_backing(
+    requested: MemoryBacking,
+    sysfs_dir: &str,
+    needed_hugepages: usize,
+) -> MemoryBacking {
+    let free_hugepages = fs::read_to_string(format!(
+        "/sys/kernel/mm/hugepages/{sysfs_dir}/free_hugepages"
+    ))
+    .map(|free| free.trim().parse().unwrap())
+    .unwrap_or(0);
+    if free_hugepages >= needed_hugepages {
+        requested
+    } else {
+        MemoryBacking::Regular
+    }
+}
@@ -0,0 +1,91 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::umem::region::{MmapRegion, UmemRegion};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use rustix::fs::{MemfdFlags, ftruncate, memfd_create};
+use rustix::io::pread;
+use rustix::param::page_size;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn memory_region() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("region", 6)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    // Stands in for memory shared with another process, which would map the same memfd.
+    let memfd = memfd_create("umem", MemfdFlags::CLOEXEC).unwrap();
+    ftruncate(&memfd, (CHUNK_NUM * CHUNK_SIZE) as u64).unwrap();
+
+    // Safety: The memfd isn't truncated or mapped anywhere else.
+    let too_small = unsafe { MmapRegion::new(&memfd, 0, page_size()) }.unwrap();
+    assert!(matches!(
+        Umem::<Marker, CHUNK_SIZE>::builder()
+            .memory_region(too_small)
+            .build(CHUNK_NUM),
+        Err(Error::InvalidUmemRegion { .. })
+    ));
+
+    // Safety: The memfd isn't truncated, only `pread` below reads from it outside of the UMEM.
+    let region = unsafe { MmapRegion::new(&memfd, 0, CHUNK_NUM * CHUNK_SIZE) }.unwrap();
+    let region_addr = region.as_ptr().as_ptr() as usize;
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::builder()
+        .memory_region(region)
+        .build(CHUNK_NUM)
+        .unwrap();
+    assert_eq!(umem.memory_backing(), None);
+
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello memory region".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        received = rings.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no frame received into the memory region");
+
+    // The frame is visible through the memfd without copying it out of the UMEM.
+    let chunk_offset = received.memory().as_ptr() as usize - region_addr;
+    let data = &received.memory()[received.data_offset()..][..received.length()];
+    let mut read_through_memfd = vec![0; data.len()];
+    pread(
+        &memfd,
+        &mut read_through_memfd,
+        (chunk_offset + received.data_offset()) as u64,
+    )
+    .unwrap();
+    assert_eq!(read_through_memfd, data);
+}
@@ -0,0 +1,112 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair_with};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::descriptor::packet::Packet;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use rustix::net::xdp::XdpDescOptions;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 128;
+
+const RING_SIZE: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const MTU: u32 = 9000;
+const PAYLOAD_LENGTH: usize = 6000;
+
+const DATA_OFFSET: usize = 256;
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn multi_buffer() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair_with("multibuf", 8, 1, Some(MTU))).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .multi_buffer(true)
+        .build::<RING_SIZE>()
+        .unwrap()
+    else {
+        panic!("Failed to get rings");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    // RX: the UDP datagram doesn't fit into a single chunk.
+    let payload: Vec<u8> = (0..PAYLOAD_LENGTH).map(|byte| byte as u8).collect();
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, payload.clone());
+        thread::sleep(Duration::from_millis(100));
+        while let Some(packet) = rings.rx_ring().pop_packet() {
+            if packet.len() > PAYLOAD_LENGTH {
+                received = Some(packet);
+                break;
+            }
+        }
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no multi-buffer packet received");
+
+    assert!(received.frames().len() > 1);
+    let (last, chained) = received.frames().split_last().unwrap();
+    assert!(
+        chained
+            .iter()
+            .all(|frame| frame.options().contains(XdpDescOptions::XDP_PKT_CONTD))
+    );
+    assert!(!last.options().contains(XdpDescOptions::XDP_PKT_CONTD));
+    assert!(received.to_vec().ends_with(&payload));
+
+    // TX: a packet without frames is rejected instead of pushing nothing.
+    assert!(
+        rings
+            .tx_ring()
+            .push_packet(Packet::new(Vec::new()))
+            .is_err()
+    );
+
+    // TX: the frame is split across chunks and chained by the continuation flag.
+    let frame = received.to_vec();
+    let packet = Packet::from_slice(&frame, DATA_OFFSET, &mut descriptors).unwrap();
+    let frames = packet.frames().len();
+    assert_eq!(frames, frame.len().div_ceil(CHUNK_SIZE - DATA_OFFSET));
+    assert_eq!(packet.to_vec(), frame);
+    rings.tx_ring().push_packet(packet).unwrap();
+
+    let mut completed = Vec::new();
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        rings
+            .completion_ring()
+            .pop_batch(frames as u32, &mut completed);
+        if completed.len() == frames {
+            break;
+        }
+    }
+    assert_eq!(completed.len(), frames, "multi-buffer packet wasn't sent");
+}
@@ -0,0 +1,80 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::descriptor::{FillCompFrameDescriptor, RxTxFrameDescriptor};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 8;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const FRAME_LENGTH: u32 = 64;
+// Not a divisor of the ring size, so batches start at different ring positions and wrap around its end.
+const BATCH: usize = 5;
+const ROUNDS: usize = 4;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn peek_release() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("peek", 24)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    let (_, completion_ring, _, tx_ring) = rings.rings();
+
+    let mut tx_descriptors: Vec<_> = descriptors.into_iter().map(tx_frame).collect();
+    for _ in 0..ROUNDS {
+        let mut batch = tx_descriptors.split_off(tx_descriptors.len() - BATCH);
+        assert_eq!(tx_ring.push_batch(&mut batch), BATCH as u32);
+
+        // Peeking without taking descriptors doesn't release anything.
+        for _ in 0..10 {
+            tx_ring.poke().unwrap();
+            if completion_ring.peek(BATCH as u32).available() == BATCH as u32 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        // Only the descriptors taken from the batch are released.
+        let mut batch = completion_ring.peek(3);
+        assert_eq!(batch.available(), 3);
+        let mut completed: Vec<_> = batch.by_ref().take(2).collect();
+        assert_eq!(batch.read(), 2);
+        batch.release();
+
+        // Asking for more than is filled peeks the rest, including the entry left behind.
+        let batch = completion_ring.peek(RING_SIZE as u32);
+        assert_eq!(batch.available(), BATCH as u32 - 2);
+        completed.extend(batch);
+        assert_eq!(completion_ring.peek(1).available(), 0);
+
+        tx_descriptors.splice(0..0, completed.into_iter().map(tx_frame));
+    }
+    assert_eq!(tx_descriptors.len(), CHUNK_NUM);
+}
+
+fn tx_frame(
+    descriptor: FillCompFrameDescriptor<'_, Marker, CHUNK_SIZE>,
+) -> RxTxFrameDescriptor<'_, Marker, CHUNK_SIZE> {
+    let mut descriptor = RxTxFrameDescriptor::from(descriptor);
+    descriptor.set_length(FRAME_LENGTH).unwrap();
+    descriptor
+}
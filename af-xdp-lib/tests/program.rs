@@ -0,0 +1,87 @@
+mod utils;
+
+use crate::utils::setup::{Marker, device_id, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::program::{AttachMode, XdpProgram};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::Rings;
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn program() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("program", 21)).await
+}
+
+// The program shipped with the crate replaces the one loaded by the test setup.
+pub fn test(_bpf: Ebpf, veth: &mut VethPair) {
+    let device_id = device_id(veth);
+
+    let mut program = XdpProgram::attach(device_id, AttachMode::Generic).unwrap();
+    assert_eq!(program.device_id(), device_id);
+
+    // Only one program can be attached to the device in a mode.
+    assert!(matches!(
+        XdpProgram::attach(device_id, AttachMode::Generic),
+        Err(Error::XdpProgramAttach { .. })
+    ));
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = program.xsk_map_storage(&umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello program".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        rings.fill_ring().poke().unwrap();
+        received = rings.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no frame received");
+
+    rings.tx_ring().push(received).unwrap();
+    let mut completed = Vec::new();
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        rings
+            .completion_ring()
+            .pop_batch(RING_SIZE as u32, &mut completed);
+        if !completed.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(completed.len(), 1, "frame wasn't sent");
+
+    drop(rings);
+
+    // Dropping the program detaches it.
+    drop(program);
+    XdpProgram::attach(device_id, AttachMode::Generic).unwrap();
+}
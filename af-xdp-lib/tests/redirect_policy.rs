@@ -0,0 +1,131 @@
+mod utils;
+
+use crate::utils::setup::{Marker, device_id, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::program::{AttachMode, FallbackAction, SocketIndex, XdpProgram};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::Rings;
+use aya::Ebpf;
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 128;
+
+const RING_SIZE: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+const SOCKETS_PER_QUEUE: u32 = 2;
+const FLOWS: u16 = 16;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn redirect_policy() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("redirect", 22)).await
+}
+
+// The program shipped with the crate replaces the one loaded by the test setup.
+pub fn test(_bpf: Ebpf, veth: &mut VethPair) {
+    let device_id = device_id(veth);
+
+    let recipient =
+        UdpSocket::bind(SocketAddr::new(veth.outside_veth_ip.into(), RECIPIENT_PORT)).unwrap();
+    recipient
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    let mut buffer = [0; 64];
+
+    // By default, packets without a socket are passed on to the network stack.
+    let program = XdpProgram::attach(device_id, AttachMode::Generic).unwrap();
+    let mut passed = false;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello pass".to_vec());
+        if recipient.recv(&mut buffer).is_ok() {
+            passed = true;
+            break;
+        }
+    }
+    assert!(passed, "packet wasn't passed");
+    drop(program);
+
+    let program = XdpProgram::builder()
+        .fallback_action(FallbackAction::Drop)
+        .attach(device_id, AttachMode::Generic)
+        .unwrap();
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello drop".to_vec());
+    }
+    thread::sleep(Duration::from_millis(100));
+    drop(program);
+
+    // Without a program, a marker sent after the dropped packets is received after any of them that weren't
+    // dropped.
+    let mut marker = false;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello marker".to_vec());
+        while let Ok(length) = recipient.recv(&mut buffer) {
+            assert_ne!(&buffer[..length], b"hello drop", "packet wasn't dropped");
+            if &buffer[..length] == b"hello marker" {
+                marker = true;
+                break;
+            }
+        }
+        if marker {
+            break;
+        }
+    }
+    assert!(marker, "marker wasn't received");
+
+    // Flows of one queue are spread across its sockets.
+    let mut program = XdpProgram::builder()
+        .fallback_action(FallbackAction::Drop)
+        .socket_index(SocketIndex::FlowHash {
+            sockets_per_queue: SOCKETS_PER_QUEUE,
+        })
+        .attach(device_id, AttachMode::Generic)
+        .unwrap();
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = program.xsk_map_storage(&umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut first) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, 0) else {
+        panic!("Failed to get rings");
+    };
+    let Rings::Two(mut second) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, 1) else {
+        panic!("Failed to get rings");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        first.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = [0; SOCKETS_PER_QUEUE as usize];
+    for _ in 0..10 {
+        for flow in 0..FLOWS {
+            veth.send_from_ns(BIND_PORT + flow, RECIPIENT_PORT, b"hello flow".to_vec());
+        }
+        thread::sleep(Duration::from_millis(200));
+        first.fill_ring().poke().unwrap();
+        while first.rx_ring().pop().is_some() {
+            received[0] += 1;
+        }
+        while second.rx_ring().pop().is_some() {
+            received[1] += 1;
+        }
+        if received.iter().all(|&count| count > 0) {
+            break;
+        }
+    }
+    assert!(
+        received.iter().all(|&count| count > 0),
+        "flows weren't spread across the sockets: {received:?}"
+    );
+}
@@ -0,0 +1,92 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::descriptor::RxTxFrameDescriptor;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 16;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const FRAME_LENGTH: u32 = 64;
+const SENT: usize = 5;
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn ring_counts() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("counts", 25)).await
+}
+
+// The counts owned by the kernel side of a ring have to be up to date without popping or pushing in between.
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+    let (fill_ring, completion_ring, rx_ring, tx_ring) = rings.rings();
+
+    // The kernel consumes TX entries and produces completions.
+    let mut tx_descriptors: Vec<_> = descriptors
+        .split_off(descriptors.len() - SENT)
+        .into_iter()
+        .map(|descriptor| {
+            let mut descriptor = RxTxFrameDescriptor::from(descriptor);
+            descriptor.set_length(FRAME_LENGTH).unwrap();
+            descriptor
+        })
+        .collect();
+    assert_eq!(completion_ring.free_entries(), RING_SIZE as u32);
+    assert_eq!(tx_ring.push_batch(&mut tx_descriptors), SENT as u32);
+    assert_eq!(tx_ring.filled_entries(), SENT as u32);
+    assert!(!tx_ring.is_empty());
+
+    for _ in 0..10 {
+        tx_ring.poke().unwrap();
+        if completion_ring.free_entries() == (RING_SIZE - SENT) as u32 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(completion_ring.free_entries(), (RING_SIZE - SENT) as u32);
+    assert_eq!(tx_ring.filled_entries(), 0);
+    assert!(tx_ring.is_empty());
+
+    // The kernel consumes fill entries and produces RX entries.
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        fill_ring.push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+    assert_eq!(fill_ring.filled_entries(), RING_SIZE as u32);
+    assert_eq!(rx_ring.free_entries(), RING_SIZE as u32);
+
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello counts".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        fill_ring.poke().unwrap();
+        if rx_ring.free_entries() < RING_SIZE as u32 {
+            break;
+        }
+    }
+    assert!(
+        rx_ring.free_entries() < RING_SIZE as u32,
+        "packet wasn't received"
+    );
+    assert!(fill_ring.filled_entries() < RING_SIZE as u32);
+}
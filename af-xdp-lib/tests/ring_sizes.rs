@@ -0,0 +1,101 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::socket::Direction;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 128;
+
+const FILL_SIZE: usize = 64;
+const COMPLETION_SIZE: usize = 16;
+const RX_SIZE: usize = 32;
+const TX_SIZE: usize = 16;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn ring_sizes() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("ring_sizes", 16)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Ok(Rings::Four(mut rings)) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .build_sized::<FILL_SIZE, COMPLETION_SIZE, RX_SIZE, TX_SIZE>()
+    else {
+        panic!("Failed to get rings");
+    };
+
+    // The fill ring holds more frames than the RX ring.
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - FILL_SIZE - RX_SIZE);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        FILL_SIZE as u32
+    );
+    assert_eq!(fill_descriptors.len(), RX_SIZE);
+    assert!(rings.fill_ring().is_full());
+    assert_eq!(rings.tx_ring().free_entries(), TX_SIZE as u32);
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello ring sizes".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        rings.fill_ring().poke().unwrap();
+        received = rings.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no frame received");
+
+    rings.tx_ring().push(received).unwrap();
+    assert_eq!(rings.tx_ring().free_entries(), TX_SIZE as u32 - 1);
+    let mut completed = Vec::new();
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        rings
+            .completion_ring()
+            .pop_batch(COMPLETION_SIZE as u32, &mut completed);
+        if !completed.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(completed.len(), 1, "frame wasn't sent");
+
+    // Sockets with a single direction only get the rings they need, with the sizes given for them. Sharing the queue,
+    // they use the fill and completion ring of the first socket.
+    let Ok(Rings::Tx(mut tx_only)) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .direction(Direction::Tx)
+        .build_sized::<FILL_SIZE, COMPLETION_SIZE, RX_SIZE, TX_SIZE>()
+    else {
+        panic!("Failed to get TX-only rings");
+    };
+    assert_eq!(tx_only.tx_ring().size(), TX_SIZE as u32);
+
+    let Ok(Rings::Rx(mut rx_only)) = xsk_map
+        .socket_builder(QUEUE_ID, 1)
+        .direction(Direction::Rx)
+        .build_sized::<FILL_SIZE, COMPLETION_SIZE, RX_SIZE, TX_SIZE>()
+    else {
+        panic!("Failed to get RX-only rings");
+    };
+    assert_eq!(rx_only.rx_ring().size(), RX_SIZE as u32);
+}
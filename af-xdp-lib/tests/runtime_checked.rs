@@ -0,0 +1,59 @@
+mod utils;
+
+use crate::utils::setup::{setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::umem::{QueueId, RuntimeChecked, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn runtime_checked() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("runtime", 19)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    // Any number of UMEMs can share the marker.
+    let (umems, mut tokens): (Vec<_>, Vec<_>) = (0..3)
+        .map(|_| Umem::<RuntimeChecked, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap())
+        .unzip();
+
+    // The token of another UMEM doesn't hand out descriptors.
+    let foreign_token = tokens.pop().unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| umems[0].descriptors(foreign_token))).is_err());
+
+    let mut foreign_descriptors = umems[1].descriptors(tokens.pop().unwrap());
+    let mut descriptors = umems[0].descriptors(tokens.pop().unwrap());
+
+    let xsk_map = XskMapStorage::new(socks, device_id, &umems[0]);
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+
+    rings.fill_ring().push(descriptors.pop().unwrap()).unwrap();
+
+    // Descriptors of another UMEM are rejected by the rings, singly and in batches.
+    let foreign_descriptor = foreign_descriptors.pop().unwrap();
+    assert!(
+        catch_unwind(AssertUnwindSafe(|| rings
+            .fill_ring()
+            .push(foreign_descriptor)))
+        .is_err()
+    );
+    assert!(
+        catch_unwind(AssertUnwindSafe(|| {
+            rings.fill_ring().push_batch(&mut foreign_descriptors)
+        }))
+        .is_err()
+    );
+}
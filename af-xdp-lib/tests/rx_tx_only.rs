@@ -0,0 +1,83 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::socket::Direction;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn rx_tx_only() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("rx_tx_only", 17)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    // The first socket of the UMEM gets the fill and completion ring.
+    let Ok(Rings::FillCompTx(mut tx)) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .direction(Direction::Tx)
+        .build::<RING_SIZE>()
+    else {
+        panic!("Failed to get TX-only rings");
+    };
+
+    // The TX-only socket isn't registered, so the index is still free. Sharing the queue, the RX-only socket uses
+    // the fill ring of the TX-only socket.
+    let Ok(Rings::Rx(mut rx)) = xsk_map
+        .socket_builder(QUEUE_ID, QUEUE_ID.0)
+        .direction(Direction::Rx)
+        .build::<RING_SIZE>()
+    else {
+        panic!("Failed to get RX-only rings");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        tx.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello rx only".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        rx.rx_ring().poke().unwrap();
+        received = rx.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let received = received.expect("no frame received");
+
+    tx.tx_ring().push(received).unwrap();
+    let mut completed = Vec::new();
+    for _ in 0..10 {
+        tx.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        tx.completion_ring().pop_batch(1, &mut completed);
+        if !completed.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(completed.len(), 1, "frame wasn't sent");
+}
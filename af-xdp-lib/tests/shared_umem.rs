@@ -0,0 +1,97 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair_with};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 128;
+
+const RING_SIZE: usize = 32;
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn shared_umem() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair_with("shared_umem", 18, 2, None)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    // The first socket of each queue gets a fill and completion ring, the XDP program redirects to the socket at
+    // the index of the queue.
+    let Rings::Four(mut queue_0) = xsk_map.rings::<RING_SIZE>(QueueId(0), 0) else {
+        panic!("Failed to get rings of queue 0");
+    };
+    let Rings::Four(mut queue_1) = xsk_map.rings::<RING_SIZE>(QueueId(1), 1) else {
+        panic!("Failed to get rings of queue 1");
+    };
+
+    // Later sockets share the fill and completion ring of their queue.
+    let Rings::Two(_shared_0) = xsk_map.rings::<RING_SIZE>(QueueId(0), 2) else {
+        panic!("Failed to get shared rings of queue 0");
+    };
+    let Rings::Two(_shared_1) = xsk_map.rings::<RING_SIZE>(QueueId(1), 3) else {
+        panic!("Failed to get shared rings of queue 1");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        queue_0.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        queue_1.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = false;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello shared umem".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        queue_0.fill_ring().poke().unwrap();
+        queue_1.fill_ring().poke().unwrap();
+        if queue_0.rx_ring().pop().is_some() || queue_1.rx_ring().pop().is_some() {
+            received = true;
+            break;
+        }
+    }
+    assert!(received, "no frame received");
+
+    // The shared socket keeps the fill and completion ring of queue 1 bound, but they were dropped with their ring
+    // set, so a new socket can neither share them nor get its own.
+    drop(queue_1);
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QueueId(1), 1),
+        Err(Error::FillCompRingsInUse {
+            queue_id: QueueId(1),
+            ..
+        })
+    ));
+
+    // Once the last socket of queue 1 is closed, a new socket gets its own fill and completion ring again. The
+    // kernel releases the queue asynchronously.
+    drop(_shared_1);
+    let mut rebound = false;
+    for _ in 0..10 {
+        if let Ok(Rings::Four(_)) = xsk_map.try_rings::<RING_SIZE>(QueueId(1), 1) {
+            rebound = true;
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert!(rebound, "queue 1 wasn't released");
+}
@@ -0,0 +1,50 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::error::Error;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, SetElementError, XskMapStorage};
+use af_xdp_test_common::SOCKS_MAP_SIZE;
+use aya::Ebpf;
+
+const RING_SIZE: usize = 64;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn try_rings() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("try_rings", 2)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, _descriptors_token) = Umem::<Marker, 4096>::new(0, 1024).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+
+    // The veth only has a single queue.
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QueueId(5), 0),
+        Err(Error::Bind {
+            queue_id: QueueId(5),
+            ..
+        })
+    ));
+
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QueueId(0), SOCKS_MAP_SIZE),
+        Err(Error::XskMapRegistration { index, .. }) if index == SOCKS_MAP_SIZE
+    ));
+
+    // The failed attempts above must not leave anything behind that prevents setting up the UMEM socket.
+    let rings = xsk_map.try_rings::<RING_SIZE>(QueueId(0), 0);
+    assert!(matches!(rings, Ok(Rings::Four(_))));
+
+    assert!(matches!(
+        xsk_map.try_rings::<RING_SIZE>(QueueId(0), 0),
+        Err(Error::XskMapRegistration {
+            index: 0,
+            source: SetElementError::Occupied { index: 0, .. },
+        })
+    ));
+}
@@ -0,0 +1,103 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::descriptor::RxTxFrameDescriptor;
+use af_xdp_lib::descriptor::tx_metadata::TxMetadata;
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 64;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const DATA_OFFSET: usize = 256;
+
+// Ethernet + IPv4 header in front of the UDP header.
+const CSUM_START: u16 = 14 + 20;
+// Offset of the checksum field in the UDP header.
+const CSUM_OFFSET: u16 = 6;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn tx_metadata() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("txmeta", 7)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::builder()
+        .tx_metadata(true)
+        .build(CHUNK_NUM)
+        .unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+
+    // The metadata has to fit between the chunk start and the data.
+    let mut no_room: RxTxFrameDescriptor<_, CHUNK_SIZE> = descriptors.pop().unwrap().into();
+    no_room.set_addr_and_length(8, 60).unwrap();
+    assert!(no_room.set_tx_metadata(TxMetadata::default()).is_err());
+
+    let mut with_metadata = udp_frame(descriptors.pop().unwrap().into());
+    let metadata = TxMetadata::default().request_checksum(CSUM_START, CSUM_OFFSET);
+    with_metadata.set_tx_metadata(metadata).unwrap();
+    assert_eq!(
+        with_metadata.tx_metadata().unwrap().checksum(),
+        Some((CSUM_START, CSUM_OFFSET))
+    );
+
+    // Frames without metadata keep working on a UMEM with TX metadata enabled.
+    let without_metadata = udp_frame(descriptors.pop().unwrap().into());
+
+    rings.tx_ring().push(with_metadata).unwrap();
+    rings.tx_ring().push(without_metadata).unwrap();
+
+    let mut completed = Vec::new();
+    for _ in 0..10 {
+        rings.tx_ring().poke().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        rings.completion_ring().pop_batch(2, &mut completed);
+        if completed.len() == 2 {
+            break;
+        }
+    }
+    assert_eq!(completed.len(), 2, "frames weren't sent");
+
+    // The metadata stays readable through the completed descriptor.
+    let checksums: Vec<_> = completed
+        .iter()
+        .filter_map(|descriptor| descriptor.tx_metadata()?.checksum())
+        .collect();
+    assert_eq!(checksums, [(CSUM_START, CSUM_OFFSET)]);
+}
+
+fn udp_frame<Marker>(
+    mut descriptor: RxTxFrameDescriptor<'_, Marker, CHUNK_SIZE>,
+) -> RxTxFrameDescriptor<'_, Marker, CHUNK_SIZE> {
+    #[rustfmt::skip]
+    let frame: [u8; 42] = [
+        // Ethernet: broadcast destination, locally administered source, IPv4.
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x08, 0x00,
+        // IPv4: 28 bytes total length, TTL 64, UDP, 10.7.0.4 -> 10.7.0.3.
+        0x45, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00,
+        10, 7, 0, 4, 10, 7, 0, 3,
+        // UDP: port 10000 -> 1777, 8 bytes length, checksum left to the offload.
+        0x27, 0x10, 0x06, 0xf1, 0x00, 0x08, 0x00, 0x00,
+    ];
+    descriptor
+        .set_addr_and_length(DATA_OFFSET, frame.len() as u32)
+        .unwrap();
+    descriptor.memory_mut()[DATA_OFFSET..][..frame.len()].copy_from_slice(&frame);
+    descriptor
+}
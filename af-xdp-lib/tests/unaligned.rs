@@ -0,0 +1,88 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::umem::{ChunkMode, QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::thread;
+use std::time::Duration;
+
+// Not a power of two, only valid in unaligned chunk mode.
+const CHUNK_SIZE: usize = 3000;
+
+const RING_SIZE: usize = 64;
+
+const HEADROOM: u32 = 100;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+const ETHER_TYPE_IPV4: [u8; 2] = [0x08, 0x00];
+const ETHER_TYPE_ARP: [u8; 2] = [0x08, 0x06];
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn unaligned() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("unaligned", 4)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    // Aligned chunks have to be a power of two in size.
+    assert!(
+        Umem::<Marker, CHUNK_SIZE>::builder()
+            .build(RING_SIZE)
+            .is_err()
+    );
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::builder()
+        .headroom(HEADROOM)
+        .chunk_mode(ChunkMode::Unaligned)
+        .build(RING_SIZE)
+        .unwrap();
+    assert_eq!(umem.chunk_mode(), ChunkMode::Unaligned);
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut descriptors),
+        RING_SIZE as u32
+    );
+
+    let mut received = None;
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello unaligned".to_vec());
+        thread::sleep(Duration::from_millis(100));
+        received = rings.rx_ring().pop();
+        if received.is_some() {
+            break;
+        }
+    }
+    let mut received = received.expect("no frame received in unaligned mode");
+
+    // The data offset is decoded from the upper bits of the address, it must point at the start of the Ethernet
+    // frame within the chunk.
+    let data_offset = received.data_offset();
+    assert!(data_offset >= HEADROOM as usize);
+    assert!(data_offset + received.length() <= CHUNK_SIZE);
+    let ether_type = [
+        received.memory()[data_offset + 12],
+        received.memory()[data_offset + 13],
+    ];
+    assert!(ether_type == ETHER_TYPE_IPV4 || ether_type == ETHER_TYPE_ARP);
+
+    // Moving the data keeps the descriptor within the same chunk.
+    received.set_addr(HEADROOM as usize).unwrap();
+    assert_eq!(received.data_offset(), HEADROOM as usize);
+    assert!(received.set_addr_and_length(CHUNK_SIZE - 10, 11).is_err());
+
+    rings.fill_ring().push(received.into()).unwrap();
+}
@@ -1,2 +1,3 @@
 pub mod ebpf;
+pub mod setup;
 pub mod veth_netlink;
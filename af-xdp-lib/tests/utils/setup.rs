@@ -0,0 +1,64 @@
+#![allow(dead_code)]
+
+use crate::utils::veth_netlink::{VethConfig, VethPair};
+use af_xdp_lib::umem::DeviceId;
+use aya::Ebpf;
+use aya::maps::{MapData, XskMap};
+use aya::programs::{Xdp, XdpFlags};
+use rustix::net::{AddressFamily, SocketType, netdevice, socket};
+use std::net::Ipv4Addr;
+use std::os::fd::AsFd;
+
+/// Marker of the UMEM of a test.
+pub struct Marker;
+
+/// Creates a veth pair with one queue in `10.<subnet>.0.0/24`, `name` has to be unique across all tests and at most
+/// 13 bytes long.
+pub fn veth_pair(name: &str, subnet: u8) -> impl Future<Output = VethPair> {
+    veth_pair_with(name, subnet, 1, None)
+}
+
+/// Creates a veth pair with `queues` RX and TX queues and the MTU if given, see [`veth_pair`].
+pub fn veth_pair_with(
+    name: &str,
+    subnet: u8,
+    queues: u32,
+    mtu: Option<u32>,
+) -> impl Future<Output = VethPair> {
+    let config = |prefix: &str, host: u8| {
+        let ip = Ipv4Addr::new(10, subnet, 0, host);
+        let config = VethConfig::new(format!("{prefix}_{name}"), ip, queues, queues);
+        match mtu {
+            Some(mtu) => config.with_mtu(mtu),
+            None => config,
+        }
+    };
+    VethPair::new(format!("ns_{name}"), config("n", 3), config("o", 4))
+}
+
+/// Attaches the `redirect_sock` program to the outside veth in generic mode.
+///
+/// Returns the `SOCKS` map of the program and the device id of the outside veth. The program is detached when `bpf`
+/// is dropped.
+pub fn setup(bpf: &mut Ebpf, veth: &VethPair) -> (XskMap<MapData>, DeviceId) {
+    let socks = bpf.take_map("SOCKS").unwrap().try_into().unwrap();
+    let program: &mut Xdp = bpf
+        .program_mut("redirect_sock")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    program.load().unwrap();
+    program
+        .attach(&veth.outside_veth_name, XdpFlags::SKB_MODE)
+        .unwrap();
+
+    (socks, device_id(veth))
+}
+
+/// Returns the device id of the outside veth.
+pub fn device_id(veth: &VethPair) -> DeviceId {
+    let name_to_index_socket = socket(AddressFamily::INET, SocketType::DGRAM, None).unwrap();
+    DeviceId(
+        netdevice::name_to_index(name_to_index_socket.as_fd(), &veth.outside_veth_name).unwrap(),
+    )
+}
@@ -19,6 +19,7 @@ pub struct VethConfig {
     pub ip: Ipv4Addr,
     pub rx_count: u32,
     pub tx_count: u32,
+    pub mtu: Option<u32>,
 }
 
 impl VethConfig {
@@ -28,8 +29,14 @@ impl VethConfig {
             ip,
             rx_count,
             tx_count,
+            mtu: None,
         }
     }
+
+    pub fn with_mtu(mut self, mtu: u32) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
 }
 
 pub struct VethPair {
@@ -81,6 +88,13 @@ impl VethPair {
 
         Self::add_address(namespaced_veth_index, namespaced_veth.ip, &netns_handle).await;
 
+        if let Some(mtu) = outside_veth.mtu {
+            Self::set_mtu(outside_veth_index, mtu, &handle).await;
+        }
+        if let Some(mtu) = namespaced_veth.mtu {
+            Self::set_mtu(namespaced_veth_index, mtu, &netns_handle).await;
+        }
+
         Self::set_up(outside_veth_index, &handle).await;
         Self::set_up(namespaced_veth_index, &netns_handle).await;
 
@@ -165,6 +179,15 @@ impl VethPair {
             .unwrap();
     }
 
+    async fn set_mtu(veth_index: u32, mtu: u32, handle: &Handle) {
+        handle
+            .link()
+            .set(LinkUnspec::new_with_index(veth_index).mtu(mtu).build())
+            .execute()
+            .await
+            .unwrap();
+    }
+
     async fn add_neighbour(veth_index: u32, veth_mac: &[u8], veth_ip: Ipv4Addr, handle: &Handle) {
         handle
             .neighbours()
@@ -0,0 +1,98 @@
+mod utils;
+
+use crate::utils::setup::{Marker, setup, veth_pair};
+use crate::utils::veth_netlink::VethPair;
+use af_xdp_lib::ring::wait::{Interest, Ready, WaitSet};
+use af_xdp_lib::umem::{QueueId, Umem};
+use af_xdp_lib::xsk_map::{Rings, XskMapStorage};
+use aya::Ebpf;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_NUM: usize = 64;
+
+const RING_SIZE: usize = 32;
+
+const QUEUE_ID: QueueId = QueueId(0);
+
+const BIND_PORT: u16 = 10000;
+const RECIPIENT_PORT: u16 = 1777;
+
+const WAIT: Duration = Duration::from_millis(100);
+
+const XDP_TOKEN: u64 = 1;
+const CONTROL_TOKEN: u64 = 2;
+
+#[cfg(not(miri))]
+#[tokio::test(flavor = "multi_thread")]
+async fn wait() -> Result<(), anyhow::Error> {
+    utils::ebpf::ebpf_test(test, veth_pair("wait", 10)).await
+}
+
+pub fn test(mut bpf: Ebpf, veth: &mut VethPair) {
+    let (socks, device_id) = setup(&mut bpf, veth);
+
+    let (umem, descriptors_token) = Umem::<Marker, CHUNK_SIZE>::new(0, CHUNK_NUM).unwrap();
+    let xsk_map = XskMapStorage::new(socks, device_id, &umem);
+    let mut descriptors = umem.descriptors(descriptors_token);
+
+    let Rings::Four(mut rings) = xsk_map.rings::<RING_SIZE>(QUEUE_ID, QUEUE_ID.0) else {
+        panic!("Failed to get rings");
+    };
+
+    let mut fill_descriptors = descriptors.split_off(descriptors.len() - RING_SIZE);
+    assert_eq!(
+        rings.fill_ring().push_batch(&mut fill_descriptors),
+        RING_SIZE as u32
+    );
+
+    // The TX ring is empty and nothing was sent yet.
+    assert!(rings.tx_ring().wait_writable(Some(WAIT)).unwrap());
+    assert!(!rings.rx_ring().wait_readable(Some(WAIT)).unwrap());
+
+    let control = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut wait_set = WaitSet::new(4).unwrap();
+    wait_set
+        .add_ring(rings.rx_ring(), XDP_TOKEN, Interest::Readable)
+        .unwrap();
+    wait_set
+        .add(&control, CONTROL_TOKEN, Interest::Readable)
+        .unwrap();
+    assert_eq!(wait_set.wait(Some(WAIT)).unwrap().count(), 0);
+
+    // A socket that isn't an XDP socket wakes up the set.
+    control
+        .send_to(b"control", control.local_addr().unwrap())
+        .unwrap();
+    let ready: Vec<Ready> = wait_set.wait(Some(WAIT)).unwrap().collect();
+    assert_eq!(
+        ready,
+        [Ready {
+            token: CONTROL_TOKEN,
+            readable: true,
+            writable: false
+        }]
+    );
+    control.recv(&mut [0; 16]).unwrap();
+    wait_set.remove(&control).unwrap();
+
+    let mut ready = Vec::new();
+    for _ in 0..10 {
+        veth.send_from_ns(BIND_PORT, RECIPIENT_PORT, b"hello wait".to_vec());
+        ready.extend(wait_set.wait(Some(WAIT)).unwrap());
+        if !ready.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(
+        ready,
+        [Ready {
+            token: XDP_TOKEN,
+            readable: true,
+            writable: false
+        }]
+    );
+    assert!(rings.rx_ring().wait_readable(None).unwrap());
+    assert!(rings.rx_ring().pop().is_some());
+}
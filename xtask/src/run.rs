@@ -23,7 +23,7 @@ pub fn run(opts: Options) -> Result<(), anyhow::Error> {
 
     // run the command
     let status = Command::new("cargo")
-        .args(["test", "--", "--nocapture"])
+        .args(["test", "--all-features", "--", "--nocapture"])
         .env("CARGO_TARGET_X86_64_UNKNOWN_LINUX_GNU_RUNNER", "sudo -E")
         .status()
         .expect("failed to run the command");